@@ -0,0 +1,121 @@
+use crate::format::{NumberFormat, format_currency};
+use chrono::{DateTime, Utc};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+/// Notifications still being sent, joined by `wait_for_notifications`
+static PENDING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Name prefix of the marker files written by `claim_alert`
+pub(crate) const MARKER_PREFIX: &str = "alert-";
+
+/// Age after which `cleanup_stale_locks` removes a marker; longer than any
+/// block, so a marker outlives the block it was claimed in
+pub(crate) const MARKER_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// Highest multiple of `step` passed going from `previous` to `current`, if
+/// any. A missing previous cost (first render) or a drop (new block) never
+/// counts, so each boundary fires once.
+pub fn crossed_boundary(previous: Option<f64>, current: f64, step: f64) -> Option<f64> {
+    let previous = previous?;
+    if step <= 0.0 || current <= previous {
+        return None;
+    }
+
+    let boundary = (current / step).floor() * step;
+    (boundary > previous && boundary > 0.0).then_some(boundary)
+}
+
+/// Claim the alert for `boundary` in the block starting at `block_start`,
+/// through a marker file in `cache_dir`. Only the first of the sessions
+/// sharing the block gets `true`, so each boundary is notified once.
+pub fn claim_alert(cache_dir: &Path, block_start: DateTime<Utc>, boundary: f64) -> bool {
+    let marker = cache_dir.join(format!(
+        "{}{}-{}",
+        MARKER_PREFIX,
+        block_start.timestamp(),
+        (boundary * 100.0).round() as i64
+    ));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(marker)
+        .is_ok()
+}
+
+/// Desktop notification that the block cost passed `boundary`, sent from a
+/// background thread so the render does not wait on the notification
+/// daemon. Failures (no daemon, headless session) are ignored.
+pub fn notify_cost_crossed(boundary: f64, cost_usd: f64, number_format: Option<NumberFormat>) {
+    let body = format!(
+        "Block cost passed {} (now {})",
+        format_currency(boundary, number_format),
+        format_currency(cost_usd, number_format)
+    );
+    let handle = std::thread::spawn(move || {
+        let _ = notify_rust::Notification::new()
+            .summary("Claude usage")
+            .body(&body)
+            .show();
+    });
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push(handle);
+    }
+}
+
+/// Wait for notifications started by `notify_cost_crossed`. Call once the
+/// statusline is printed, before exiting.
+pub fn wait_for_notifications() {
+    let handles = match PENDING.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return,
+    };
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossed_boundary() {
+        // Fires on the render that passes the boundary, not after
+        assert_eq!(crossed_boundary(Some(9.5), 10.2, 10.0), Some(10.0));
+        assert_eq!(crossed_boundary(Some(10.2), 11.0, 10.0), None);
+        assert_eq!(crossed_boundary(Some(9.5), 10.0, 10.0), Some(10.0));
+
+        // Jumping several boundaries reports the highest one
+        assert_eq!(crossed_boundary(Some(4.0), 27.0, 10.0), Some(20.0));
+
+        // No previous render, a new block, or a disabled step never fire
+        assert_eq!(crossed_boundary(None, 12.0, 10.0), None);
+        assert_eq!(crossed_boundary(Some(25.0), 0.5, 10.0), None);
+        assert_eq!(crossed_boundary(Some(0.0), 0.5, 10.0), None);
+        assert_eq!(crossed_boundary(Some(9.0), 12.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_claim_alert_once_per_block() {
+        let dir = std::env::temp_dir().join("ccusage-test-alert-claim");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let block = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let next_block = DateTime::from_timestamp(1_700_018_000, 0).unwrap();
+
+        // A second session crossing the same boundary stays quiet
+        let first = claim_alert(&dir, block, 10.0);
+        let second = claim_alert(&dir, block, 10.0);
+        let next_boundary = claim_alert(&dir, block, 20.0);
+        let other_block = claim_alert(&dir, next_block, 10.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(first);
+        assert!(!second);
+        assert!(next_boundary);
+        assert!(other_block);
+    }
+}
@@ -4,17 +4,39 @@ use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
 use std::io::{ErrorKind, IsTerminal, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
-use crate::cache::get_cache_dir;
-use crate::paths::claude_config_dir;
+use crate::cache::{create_cache_dir, force_refresh, get_cache_dir};
+use crate::paths::credentials_path;
 use crate::types::{ApiUsageData, PlanType};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct UsageLimit {
     utilization: f64,
-    resets_at: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_resets_at")]
+    resets_at: Option<DateTime<Utc>>,
+}
+
+/// Accept `resets_at` as an RFC3339 string or unix seconds; anything else is `None`
+fn deserialize_resets_at<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::String(s)) => s
+            .parse::<DateTime<Utc>>()
+            .ok(),
+        Some(serde_json::Value::Number(n)) => n
+            .as_i64()
+            .or_else(|| {
+                n.as_f64()
+                    .map(|f| f as i64)
+            })
+            .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+        _ => None,
+    })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,6 +44,8 @@ struct ApiResponse {
     five_hour: UsageLimit,
     seven_day: UsageLimit,
     seven_day_sonnet: Option<UsageLimit>,
+    #[serde(default)]
+    seven_day_opus: Option<UsageLimit>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,6 +69,9 @@ struct OAuthCredentials {
     access_token: String,
     #[serde(rename = "subscriptionType")]
     subscription_type: Option<String>,
+    /// Access token expiry as unix milliseconds
+    #[serde(default, rename = "expiresAt")]
+    expires_at: Option<i64>,
 }
 
 use crate::config::CacheSettings;
@@ -80,33 +107,132 @@ impl ApiUsageResult {
     }
 }
 
-/// Get API cache file path
-fn get_api_cache_path() -> Result<PathBuf> {
+/// An account's credentials file and the usage cache kept for it
+struct Account {
+    credentials: PathBuf,
+    cache_path: PathBuf,
+}
+
+impl Account {
+    /// The logged-in account (respecting `--profile`)
+    fn default_account(api_base: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            credentials: credentials_path()?,
+            cache_path: api_cache_dir()?
+                .join(cache_file_name("api-usage-cache".to_string(), api_base)),
+        })
+    }
+
+    /// An extra account from `extra_credentials`, cached under a name
+    /// derived from its credentials path
+    fn extra(credentials: &Path, api_base: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            credentials: credentials.to_path_buf(),
+            cache_path: api_cache_dir()?.join(cache_file_name(
+                format!("api-usage-cache-{:016x}", path_hash(credentials)),
+                api_base,
+            )),
+        })
+    }
+}
+
+/// Cache file name for `stem`. Responses from a `CCUSAGE_API_BASE` override
+/// get a file of their own, so mock data never reaches the real cache.
+fn cache_file_name(stem: String, api_base: Option<&str>) -> String {
+    match api_base {
+        Some(base) => format!("{}-{:016x}.json", stem, path_hash(Path::new(base))),
+        None => format!("{}.json", stem),
+    }
+}
+
+/// FNV-1a hash of a path, stable across runs and Rust releases so that
+/// the cache file name of an account never changes
+fn path_hash(path: &Path) -> u64 {
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+fn api_cache_dir() -> Result<PathBuf> {
     let cache_dir = get_cache_dir()?;
-    fs::create_dir_all(&cache_dir)?;
-    Ok(cache_dir.join("api-usage-cache.json"))
+    create_cache_dir(&cache_dir)?;
+    Ok(cache_dir)
+}
+
+/// No OAuth login: the credentials file is absent or has no OAuth section.
+/// Expected for API key users, so never reported.
+#[derive(Debug)]
+struct NotLoggedIn(&'static str);
+
+impl std::fmt::Display for NotLoggedIn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
 }
 
+impl std::error::Error for NotLoggedIn {}
+
 fn read_credentials() -> Result<ClaudeCredentials> {
-    let creds_path = claude_config_dir()?.join(".credentials.json");
+    read_credentials_from(&credentials_path()?)
+}
 
-    let content = fs::read_to_string(&creds_path)
-        .context("Failed to read credentials - ensure you're logged in with Claude Code")?;
+fn read_credentials_from(creds_path: &Path) -> Result<ClaudeCredentials> {
+    let content = match fs::read_to_string(creds_path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(NotLoggedIn("Credentials not found - log in with Claude Code").into());
+        }
+        result => result.context("Failed to read credentials file")?,
+    };
 
     serde_json::from_str(&content).context("Failed to parse credentials file")
 }
 
-fn read_oauth_credentials() -> Result<String> {
-    let creds = read_credentials()?;
+fn read_oauth_credentials(creds_path: &Path) -> Result<String> {
+    oauth_access_token(read_credentials_from(creds_path)?)
+}
+
+fn oauth_access_token(creds: ClaudeCredentials) -> Result<String> {
     creds
         .claude_ai_oauth
         .map(|oauth| oauth.access_token)
-        .context("No OAuth credentials found - run 'claude' to login")
+        .ok_or_else(|| NotLoggedIn("No OAuth credentials found - run 'claude' to login").into())
+}
+
+/// Message worth logging for a credentials failure; nothing when the user is
+/// simply not logged in, which would otherwise be logged on every render
+fn credentials_warning(err: &anyhow::Error) -> Option<String> {
+    if err
+        .downcast_ref::<NotLoggedIn>()
+        .is_some()
+    {
+        return None;
+    }
+    Some(format!("Failed to read API credentials: {:#}", err))
+}
+
+/// Plan type and token expiry from the credentials file, read once per render
+#[derive(Debug, Clone, Copy)]
+pub struct LoginInfo {
+    pub plan_type: PlanType,
+    /// OAuth access token expiry, if the credentials record one
+    pub token_expiry: Option<DateTime<Utc>>,
+}
+
+pub fn get_login_info() -> LoginInfo {
+    login_info(read_credentials().ok())
 }
 
 pub fn get_plan_type() -> PlanType {
-    match read_credentials() {
-        Ok(creds) => match creds.claude_ai_oauth {
+    get_login_info().plan_type
+}
+
+fn login_info(creds: Option<ClaudeCredentials>) -> LoginInfo {
+    let oauth = creds.and_then(|creds| creds.claude_ai_oauth);
+    LoginInfo {
+        plan_type: match &oauth {
             Some(oauth)
                 if oauth
                     .subscription_type
@@ -116,18 +242,122 @@ pub fn get_plan_type() -> PlanType {
             }
             _ => PlanType::Api,
         },
-        Err(_) => PlanType::Api,
+        token_expiry: oauth
+            .and_then(|oauth| oauth.expires_at)
+            .and_then(DateTime::from_timestamp_millis),
     }
 }
 
-/// Fetch usage data from Anthropic API with filesystem-based caching and advisory locks
-pub fn fetch_usage(cache_settings: &CacheSettings) -> ApiUsageResult {
+/// Cache and request settings of usage fetches, built once from the config
+/// (see `StatuslineConfig::fetch_options`)
+#[derive(Debug, Clone, Copy)]
+pub struct FetchOptions<'a> {
+    pub cache: &'a CacheSettings,
+    /// Further accounts fetched alongside the logged-in one
+    pub extra_credentials: &'a [PathBuf],
+    /// Timeout of the usage request
+    pub timeout: Duration,
+    /// Claude binary whose version goes in the User-Agent
+    pub claude_binary: Option<&'a Path>,
+}
+
+/// Fetch usage data from Anthropic API with filesystem-based caching and
+/// advisory locks. With `extra_credentials`, each account is fetched in turn
+/// with its own cache and the results are combined (see `combine_accounts`).
+pub fn fetch_usage(options: &FetchOptions) -> ApiUsageResult {
+    let api_base = api_base_override();
+    let api_base = api_base.as_deref();
+    let mut results = vec![
+        Account::default_account(api_base).map_or(ApiUsageResult::Unavailable, |account| {
+            fetch_account_usage(&account, options)
+        }),
+    ];
+    if options
+        .extra_credentials
+        .is_empty()
+    {
+        return results.remove(0);
+    }
+
+    for credentials in options.extra_credentials {
+        results.push(
+            Account::extra(credentials, api_base).map_or(ApiUsageResult::Unavailable, |account| {
+                fetch_account_usage(&account, options)
+            }),
+        );
+    }
+    combine_accounts(results)
+}
+
+/// Combine per-account results. Limits are per account, so each value is
+/// the highest across accounts; `accounts` keeps every account's data
+/// labelled A, B, … in configuration order. Errors only surface when no
+/// account has data.
+fn combine_accounts(results: Vec<ApiUsageResult>) -> ApiUsageResult {
+    let mut accounts = Vec::new();
+    let mut error = None;
+    for (label, result) in ('A'..='Z').zip(results) {
+        match result {
+            ApiUsageResult::Ok(data) => accounts.push((label, data)),
+            ApiUsageResult::Unavailable => {}
+            other => {
+                error.get_or_insert(other);
+            }
+        }
+    }
+
+    let Some(mut combined) = accounts
+        .iter()
+        .map(|(_, data)| data.clone())
+        .reduce(max_usage)
+    else {
+        return error.unwrap_or(ApiUsageResult::Unavailable);
+    };
+    combined.accounts = accounts;
+    ApiUsageResult::Ok(combined)
+}
+
+/// Highest utilization of each limit, with the reset time of the account
+/// that has it
+fn max_usage(a: ApiUsageData, b: ApiUsageData) -> ApiUsageData {
+    let (five_hour_percent, five_hour_resets_at) = if b.five_hour_percent > a.five_hour_percent {
+        (b.five_hour_percent, b.five_hour_resets_at)
+    } else {
+        (a.five_hour_percent, a.five_hour_resets_at)
+    };
+    let (seven_day_percent, seven_day_resets_at) = if b.seven_day_percent > a.seven_day_percent {
+        (b.seven_day_percent, b.seven_day_resets_at)
+    } else {
+        (a.seven_day_percent, a.seven_day_resets_at)
+    };
+    let max = |x: Option<f64>, y: Option<f64>| match (x, y) {
+        (Some(x), Some(y)) => Some(x.max(y)),
+        (x, y) => x.or(y),
+    };
+
+    ApiUsageData {
+        five_hour_percent,
+        five_hour_resets_at,
+        seven_day_percent,
+        seven_day_resets_at,
+        seven_day_sonnet_percent: max(a.seven_day_sonnet_percent, b.seven_day_sonnet_percent),
+        seven_day_opus_percent: max(a.seven_day_opus_percent, b.seven_day_opus_percent),
+        accounts: Vec::new(),
+    }
+}
+
+fn fetch_account_usage(account: &Account, options: &FetchOptions) -> ApiUsageResult {
     // Check credentials first - if missing, skip network calls entirely
-    if read_oauth_credentials().is_err() {
+    if let Err(e) = read_oauth_credentials(&account.credentials) {
+        if let Some(warning) = credentials_warning(&e)
+            && std::io::stderr().is_terminal()
+        {
+            eprintln!("{}", warning);
+        }
         return ApiUsageResult::Unavailable;
     }
 
-    match fetch_usage_with_lock(cache_settings) {
+    match fetch_usage_with_lock(account, options) {
         Ok((data, _fetched_at)) => ApiUsageResult::Ok(data),
         Err(e) => {
             let msg = e.to_string();
@@ -143,18 +373,19 @@ pub fn fetch_usage(cache_settings: &CacheSettings) -> ApiUsageResult {
     }
 }
 
-fn fetch_usage_with_lock(cache_settings: &CacheSettings) -> Result<(ApiUsageData, u64)> {
-    let cache_path = get_api_cache_path()?;
+fn fetch_usage_with_lock(account: &Account, options: &FetchOptions) -> Result<(ApiUsageData, u64)> {
+    let cache_path = &account.cache_path;
 
     // Only open existing file — don't create an empty one
     match OpenOptions::new()
         .read(true)
         .write(true)
-        .open(&cache_path)
+        .open(cache_path)
     {
         Ok(mut file) => match file.try_lock_exclusive() {
             Ok(()) => {
-                let result = fetch_or_use_cache(&mut file, &cache_path, cache_settings);
+                let result =
+                    fetch_or_use_cache(&mut file, cache_path, &account.credentials, options);
                 FileExt::unlock(&file)?;
                 result
             }
@@ -173,7 +404,7 @@ fn fetch_usage_with_lock(cache_settings: &CacheSettings) -> Result<(ApiUsageData
         },
         Err(e) if e.kind() == ErrorKind::NotFound => {
             // No cache file — first run, fetch directly
-            fetch_and_write_cache(&cache_path)
+            fetch_and_write_cache(cache_path, &account.credentials, options)
         }
         Err(e) => Err(e.into()),
     }
@@ -182,8 +413,10 @@ fn fetch_usage_with_lock(cache_settings: &CacheSettings) -> Result<(ApiUsageData
 fn fetch_or_use_cache(
     file: &mut File,
     cache_path: &PathBuf,
-    cache_settings: &CacheSettings,
+    creds_path: &Path,
+    options: &FetchOptions,
 ) -> Result<(ApiUsageData, u64)> {
+    let cache_settings = options.cache;
     let metadata = file.metadata()?;
     let mtime = metadata.modified()?;
     let mtime_age = mtime
@@ -208,6 +441,7 @@ fn fetch_or_use_cache(
 
     // Return cached data if within backoff window and we have response data
     if mtime_age < Duration::from_secs(effective_fresh)
+        && !force_refresh()
         && let Some(env) = existing
     {
         if let Some(response) = env.response {
@@ -217,7 +451,7 @@ fn fetch_or_use_cache(
         anyhow::bail!("rate_limited");
     }
 
-    match fetch_api_response() {
+    match fetch_api_response(creds_path, options) {
         Ok(api_response) => {
             let now = now_epoch();
             let envelope = CacheEnvelope {
@@ -268,8 +502,12 @@ fn fetch_or_use_cache(
     }
 }
 
-fn fetch_and_write_cache(cache_path: &PathBuf) -> Result<(ApiUsageData, u64)> {
-    match fetch_api_response() {
+fn fetch_and_write_cache(
+    cache_path: &PathBuf,
+    creds_path: &Path,
+    options: &FetchOptions,
+) -> Result<(ApiUsageData, u64)> {
+    match fetch_api_response(creds_path, options) {
         Ok(api_response) => {
             let now = now_epoch();
             let envelope = CacheEnvelope {
@@ -327,49 +565,84 @@ fn read_envelope_from_file(file: &mut File) -> Result<CacheEnvelope> {
 }
 
 fn parse_api_response(api_response: ApiResponse) -> ApiUsageData {
-    let five_hour_resets_at = api_response
-        .five_hour
-        .resets_at
-        .and_then(|s| {
-            s.parse::<DateTime<Utc>>()
-                .ok()
-        });
-
-    let seven_day_resets_at = api_response
-        .seven_day
-        .resets_at
-        .and_then(|s| {
-            s.parse::<DateTime<Utc>>()
-                .ok()
-        });
-
     let seven_day_sonnet_percent = api_response
         .seven_day_sonnet
-        .map(|l| l.utilization)
-        .unwrap_or(0.0);
+        .map(|l| l.utilization);
+    let seven_day_opus_percent = api_response
+        .seven_day_opus
+        .map(|l| l.utilization);
 
     ApiUsageData {
         five_hour_percent: api_response
             .five_hour
             .utilization,
-        five_hour_resets_at,
+        five_hour_resets_at: api_response
+            .five_hour
+            .resets_at,
         seven_day_percent: api_response
             .seven_day
             .utilization,
-        seven_day_resets_at,
+        seven_day_resets_at: api_response
+            .seven_day
+            .resets_at,
         seven_day_sonnet_percent,
+        seven_day_opus_percent,
+        accounts: Vec::new(),
     }
 }
 
-fn fetch_api_response() -> Result<ApiResponse> {
-    let access_token = read_oauth_credentials()?;
-    let user_agent = crate::claude_binary::get_user_agent();
+/// Perform one usage request, bypassing and not updating the cache
+pub fn check_api(options: &FetchOptions) -> Result<ApiUsageData> {
+    fetch_api_response(&credentials_path()?, options).map(parse_api_response)
+}
 
-    let url = "https://api.anthropic.com/api/oauth/usage";
+const DEFAULT_API_BASE: &str = "https://api.anthropic.com";
+
+/// `base` without its trailing slash, if the OAuth token may be sent there:
+/// an https URL, or plain http to a loopback host
+fn allowed_api_base(base: &str) -> Option<&str> {
+    let base = base.trim_end_matches('/');
+    let url = reqwest::Url::parse(base).ok()?;
+    let host = url.host_str()?;
+    let loopback = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<std::net::IpAddr>()
+        .map_or(host == "localhost", |ip| ip.is_loopback());
+    (url.scheme() == "https" || (url.scheme() == "http" && loopback)).then_some(base)
+}
 
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()?;
+/// `CCUSAGE_API_BASE`, which points requests at a proxy or mock server,
+/// when `allowed_api_base` accepts it
+fn api_base_override() -> Option<String> {
+    let base = std::env::var("CCUSAGE_API_BASE").ok()?;
+    allowed_api_base(&base).map(str::to_string)
+}
+
+/// Usage endpoint under `base`, falling back to the default base when it is
+/// missing or not allowed
+fn usage_url(base: Option<&str>) -> String {
+    let base = base
+        .and_then(allowed_api_base)
+        .unwrap_or(DEFAULT_API_BASE);
+    format!("{}/api/oauth/usage", base)
+}
+
+fn fetch_api_response(creds_path: &Path, options: &FetchOptions) -> Result<ApiResponse> {
+    let access_token = read_oauth_credentials(creds_path)?;
+    let user_agent = crate::claude_binary::get_user_agent(options.claude_binary);
+    let url = usage_url(api_base_override().as_deref());
+
+    fetch_api_response_from(&url, &access_token, &user_agent, options.timeout)
+}
+
+fn fetch_api_response_from(
+    url: &str,
+    access_token: &str,
+    user_agent: &str,
+    timeout: Duration,
+) -> Result<ApiResponse> {
+    let client = crate::net::client(timeout)?;
 
     let response = client
         .get(url)
@@ -397,7 +670,7 @@ fn fetch_api_response() -> Result<ApiResponse> {
                     response.headers()
                 );
             }
-            anyhow::bail!("rate_limited");
+            anyhow::bail!("rate_limited (API returned status: {})", status);
         }
         anyhow::bail!("API returned status: {}", status);
     }
@@ -424,13 +697,18 @@ mod tests {
             response: Some(ApiResponse {
                 five_hour: UsageLimit {
                     utilization: utilization_5h,
-                    resets_at: Some("2025-11-01T12:00:00Z".to_string()),
+                    resets_at: "2025-11-01T12:00:00Z"
+                        .parse()
+                        .ok(),
                 },
                 seven_day: UsageLimit {
                     utilization: utilization_7d,
-                    resets_at: Some("2025-11-02T12:00:00Z".to_string()),
+                    resets_at: "2025-11-02T12:00:00Z"
+                        .parse()
+                        .ok(),
                 },
                 seven_day_sonnet: None,
+                seven_day_opus: None,
             }),
         }
     }
@@ -572,6 +850,265 @@ mod tests {
         fs::remove_dir_all(&cache_dir).unwrap();
     }
 
+    #[test]
+    fn test_credentials_expires_at() {
+        let creds: ClaudeCredentials = serde_json::from_str(
+            r#"{"claudeAiOauth": {"accessToken": "x", "subscriptionType": "max", "expiresAt": 1761998400000}}"#,
+        )
+        .unwrap();
+        let oauth = creds
+            .claude_ai_oauth
+            .unwrap();
+        assert_eq!(oauth.expires_at, Some(1_761_998_400_000));
+
+        let creds: ClaudeCredentials = serde_json::from_str(
+            r#"{"claudeAiOauth": {"accessToken": "x", "subscriptionType": "max", "expiresAt": 1761998400000}}"#,
+        )
+        .unwrap();
+        let login = login_info(Some(creds));
+        assert!(matches!(login.plan_type, PlanType::Subscription));
+        assert_eq!(
+            login.token_expiry,
+            DateTime::from_timestamp_millis(1_761_998_400_000)
+        );
+
+        let creds: ClaudeCredentials =
+            serde_json::from_str(r#"{"claudeAiOauth": {"accessToken": "x"}}"#).unwrap();
+        assert!(
+            creds
+                .claude_ai_oauth
+                .unwrap()
+                .expires_at
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_resets_at_rfc3339() {
+        let limit: UsageLimit =
+            serde_json::from_str(r#"{"utilization": 12.0, "resets_at": "2025-11-01T12:00:00Z"}"#)
+                .unwrap();
+        assert_eq!(limit.resets_at, DateTime::from_timestamp(1_761_998_400, 0));
+    }
+
+    #[test]
+    fn test_resets_at_unix_seconds() {
+        let limit: UsageLimit =
+            serde_json::from_str(r#"{"utilization": 12.0, "resets_at": 1761998400}"#).unwrap();
+        assert_eq!(limit.resets_at, DateTime::from_timestamp(1_761_998_400, 0));
+    }
+
+    #[test]
+    fn test_resets_at_malformed_is_none() {
+        for json in [
+            r#"{"utilization": 12.0, "resets_at": "next tuesday"}"#,
+            r#"{"utilization": 12.0, "resets_at": true}"#,
+            r#"{"utilization": 12.0, "resets_at": null}"#,
+            r#"{"utilization": 12.0}"#,
+        ] {
+            let limit: UsageLimit = serde_json::from_str(json).unwrap();
+            assert!(
+                limit
+                    .resets_at
+                    .is_none(),
+                "expected None for {}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_sonnet_absent_vs_zero() {
+        let limits = r#""five_hour": {"utilization": 10.0}, "seven_day": {"utilization": 20.0}"#;
+
+        let absent: ApiResponse = serde_json::from_str(&format!("{{{}}}", limits)).unwrap();
+        assert_eq!(parse_api_response(absent).seven_day_sonnet_percent, None);
+
+        let zero: ApiResponse = serde_json::from_str(&format!(
+            r#"{{{}, "seven_day_sonnet": {{"utilization": 0.0}}}}"#,
+            limits
+        ))
+        .unwrap();
+        assert_eq!(parse_api_response(zero).seven_day_sonnet_percent, Some(0.0));
+    }
+
+    #[test]
+    fn test_combine_accounts() {
+        let usage = |five_hour: f64, seven_day: f64, sonnet: Option<f64>| ApiUsageData {
+            five_hour_percent: five_hour,
+            five_hour_resets_at: DateTime::from_timestamp(five_hour as i64, 0),
+            seven_day_percent: seven_day,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: sonnet,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+
+        let combined = combine_accounts(vec![
+            ApiUsageResult::Ok(usage(40.0, 12.0, None)),
+            ApiUsageResult::Ok(usage(10.0, 30.0, Some(5.0))),
+        ]);
+        let data = combined
+            .data()
+            .unwrap();
+        assert_eq!(data.five_hour_percent, 40.0);
+        // The reset time follows the account with the highest utilization
+        assert_eq!(data.five_hour_resets_at, DateTime::from_timestamp(40, 0));
+        assert_eq!(data.seven_day_percent, 30.0);
+        assert_eq!(data.seven_day_sonnet_percent, Some(5.0));
+        let labels: Vec<char> = data
+            .accounts
+            .iter()
+            .map(|(label, _)| *label)
+            .collect();
+        assert_eq!(labels, ['A', 'B']);
+
+        // A failing account keeps its letter and does not hide the others
+        let partial = combine_accounts(vec![
+            ApiUsageResult::RateLimited,
+            ApiUsageResult::Ok(usage(10.0, 30.0, None)),
+        ]);
+        let data = partial
+            .data()
+            .unwrap();
+        assert_eq!(data.five_hour_percent, 10.0);
+        assert_eq!(data.accounts[0].0, 'B');
+
+        // Errors surface only when no account has data
+        let failed = combine_accounts(vec![
+            ApiUsageResult::Unavailable,
+            ApiUsageResult::RateLimited,
+        ]);
+        assert_eq!(failed.error_label(), Some("rate limited"));
+        assert!(matches!(
+            combine_accounts(vec![
+                ApiUsageResult::Unavailable,
+                ApiUsageResult::Unavailable
+            ]),
+            ApiUsageResult::Unavailable
+        ));
+    }
+
+    #[test]
+    fn test_path_hash_is_stable() {
+        // FNV-1a reference values: the cache name must not change between builds
+        assert_eq!(path_hash(Path::new("")), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(path_hash(Path::new("a")), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(
+            path_hash(Path::new("/home/a/.claude/.credentials.json")),
+            path_hash(Path::new("/home/b/.claude/.credentials.json"))
+        );
+    }
+
+    #[test]
+    fn test_missing_credentials_are_silent() {
+        let dir = std::env::temp_dir().join("ccusage-test-credentials");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".credentials.json");
+
+        let missing = read_credentials_from(&path).unwrap_err();
+        assert_eq!(credentials_warning(&missing), None);
+
+        fs::write(&path, "{}").unwrap();
+        let no_oauth = oauth_access_token(read_credentials_from(&path).unwrap()).unwrap_err();
+        assert_eq!(credentials_warning(&no_oauth), None);
+
+        fs::write(&path, "{not json").unwrap();
+        let corrupt = read_credentials_from(&path).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            credentials_warning(&corrupt)
+                .unwrap()
+                .contains("Failed to parse credentials file")
+        );
+    }
+
+    #[test]
+    fn test_usage_url_base_override() {
+        assert_eq!(usage_url(None), "https://api.anthropic.com/api/oauth/usage");
+        assert_eq!(
+            usage_url(Some("http://127.0.0.1:8080/")),
+            "http://127.0.0.1:8080/api/oauth/usage"
+        );
+        assert_eq!(
+            usage_url(Some("http://[::1]:8080")),
+            "http://[::1]:8080/api/oauth/usage"
+        );
+        assert_eq!(
+            usage_url(Some("https://proxy.example.com/")),
+            "https://proxy.example.com/api/oauth/usage"
+        );
+        assert_eq!(
+            usage_url(Some("not a url")),
+            "https://api.anthropic.com/api/oauth/usage"
+        );
+        // The bearer token never goes over plain http to another host
+        assert_eq!(
+            usage_url(Some("http://proxy.example.com")),
+            "https://api.anthropic.com/api/oauth/usage"
+        );
+    }
+
+    #[test]
+    fn test_cache_file_name_keyed_on_api_base() {
+        let stem = || "api-usage-cache".to_string();
+        assert_eq!(cache_file_name(stem(), None), "api-usage-cache.json");
+        let mock = cache_file_name(stem(), Some("http://127.0.0.1:8080"));
+        assert_ne!(mock, "api-usage-cache.json");
+        assert_ne!(mock, cache_file_name(stem(), Some("http://127.0.0.1:9090")));
+    }
+
+    #[test]
+    fn test_fetch_from_mock_server() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .unwrap()
+        );
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener
+                .accept()
+                .unwrap();
+            let mut request = [0u8; 4096];
+            let n = stream
+                .read(&mut request)
+                .unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).to_string();
+            let body = r#"{"five_hour":{"utilization":42.0},"seven_day":{"utilization":7.0}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        let response = fetch_api_response_from(
+            &usage_url(Some(&base)),
+            "token",
+            "claude-code/test",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let request = server
+            .join()
+            .unwrap();
+
+        assert!(request.starts_with("GET /api/oauth/usage "));
+        assert!(request.contains("authorization: Bearer token"));
+        let data = parse_api_response(response);
+        assert_eq!(data.five_hour_percent, 42.0);
+        assert_eq!(data.seven_day_percent, 7.0);
+    }
+
     #[test]
     fn test_api_usage_result_data() {
         let data = ApiUsageData {
@@ -579,7 +1116,9 @@ mod tests {
             five_hour_resets_at: None,
             seven_day_percent: 10.0,
             seven_day_resets_at: None,
-            seven_day_sonnet_percent: 5.0,
+            seven_day_sonnet_percent: Some(5.0),
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
         };
         let result = ApiUsageResult::Ok(data.clone());
         assert!(
@@ -1,15 +1,15 @@
+use crate::config::OverlapPolicy;
 use crate::paths::iter_jsonl_files_since;
 use crate::pricing::PricingFetcher;
-use crate::types::{Block, UsageData};
+use crate::types::{Block, Transcript, UsageData, UsageTokens};
 use anyhow::Result;
 use chrono::{DateTime, Duration, Timelike, Utc};
 use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-const BLOCK_DURATION_HOURS: i64 = 5;
-const FILE_LOOKBACK_HOURS: i64 = 12; // Look back 12h to catch overlapping blocks
+const FILE_LOOKBACK_BUFFER_HOURS: i64 = 7; // Extra lookback past one block to catch overlapping blocks
 const BUFREADER_CAPACITY: usize = 8192;
 
 /// Floor timestamp to the beginning of the hour in UTC
@@ -22,18 +22,35 @@ fn floor_to_hour(timestamp: DateTime<Utc>) -> DateTime<Utc> {
 }
 
 /// Group usage entries into blocks (matching TypeScript logic)
-pub fn group_into_blocks(entries: &[UsageData], pricing: &PricingFetcher) -> Result<Vec<Block>> {
+pub fn group_into_blocks(
+    entries: &[UsageData],
+    pricing: &PricingFetcher,
+    block_duration_hours: i64,
+) -> Result<Vec<Block>> {
+    group_into_blocks_at(entries, pricing, block_duration_hours, Utc::now())
+}
+
+/// Like `group_into_blocks`, with activity judged as of `now`
+fn group_into_blocks_at(
+    entries: &[UsageData],
+    pricing: &PricingFetcher,
+    block_duration_hours: i64,
+    now: DateTime<Utc>,
+) -> Result<Vec<Block>> {
     if entries.is_empty() {
         return Ok(Vec::new());
     }
 
-    let session_duration_ms = BLOCK_DURATION_HOURS * 60 * 60 * 1000; // Block duration in milliseconds
+    let session_duration_ms = block_duration_hours * 60 * 60 * 1000; // Block duration in milliseconds
     let mut blocks = Vec::new();
     let mut current_block_start: Option<DateTime<Utc>> = None;
     let mut current_block_entries: Vec<&UsageData> = Vec::new();
-    let now = Utc::now();
 
-    for entry in entries {
+    // Only billed entries open or extend blocks
+    for entry in entries
+        .iter()
+        .filter(|e| e.has_usage())
+    {
         let entry_time = DateTime::parse_from_rfc3339(&entry.timestamp)?;
         let entry_time = entry_time.with_timezone(&Utc);
 
@@ -129,94 +146,1107 @@ pub fn create_block_from_entries(
     };
 
     let mut cost_usd = 0.0;
+    let mut total_tokens = 0;
+    let mut cache_creation_tokens = 0;
+    let mut cache_read_tokens = 0;
+    let mut entry_costs = Vec::with_capacity(entries.len());
     for entry in entries {
-        cost_usd += pricing.calculate_entry_cost(entry);
+        let cost = pricing.calculate_entry_cost(entry);
+        cost_usd += cost;
+        if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+            entry_costs.push((ts.with_timezone(&Utc), cost));
+        }
+        let usage = entry.usage();
+        total_tokens += usage.input_tokens
+            + usage.output_tokens
+            + usage.cache_creation_input_tokens
+            + usage.cache_read_input_tokens;
+        cache_creation_tokens += usage.cache_creation_input_tokens;
+        cache_read_tokens += usage.cache_read_input_tokens;
     }
 
     Block {
         start_time,
         end_time,
         cost_usd,
+        total_tokens,
+        cache_creation_tokens,
+        cache_read_tokens,
         is_active,
         hours_remaining,
+        session_count: 0,
+        stale: false,
+        in_grace: false,
+        latest_source: None,
+        entry_costs,
     }
 }
 
-/// Find active billing block
-pub fn find_active_block(claude_paths: &[PathBuf], pricing: &PricingFetcher) -> Result<Block> {
-    let mut all_entries = Vec::with_capacity(1000);
-    let mut processed_hashes: HashSet<String> = HashSet::with_capacity(1000);
+/// Count distinct sources with at least one entry inside the block window.
+/// `sources` yields each entry's source index, in entry order.
+fn count_block_sessions(
+    block: &Block,
+    entries: &[UsageData],
+    sources: impl IntoIterator<Item = usize>,
+) -> usize {
+    let mut sessions = HashSet::new();
+    for (entry, source) in entries
+        .iter()
+        .zip(sources)
+    {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+            let ts = ts.with_timezone(&Utc);
+            if ts >= block.start_time && ts < block.end_time {
+                sessions.insert(source);
+            }
+        }
+    }
+    sessions.len()
+}
 
-    let now = Utc::now();
-    let file_cutoff_time = now - Duration::hours(FILE_LOOKBACK_HOURS);
-    let file_cutoff_timestamp = file_cutoff_time.timestamp();
-
-    for session_file in iter_jsonl_files_since(claude_paths, Some(file_cutoff_timestamp))? {
-        // Skip files not modified within lookback window
-        if let Ok(metadata) = fs::metadata(&session_file)
-            && let Ok(modified) = metadata.modified()
-            && let Ok(modified_duration) = modified.duration_since(std::time::UNIX_EPOCH)
-            && (modified_duration.as_secs() as i64) < file_cutoff_timestamp
+/// Index into `sources` of the newest entry inside the block window
+fn latest_block_source(block: &Block, entries: &[UsageData], sources: &[usize]) -> Option<usize> {
+    entries
+        .iter()
+        .zip(sources)
+        .rev()
+        .find(|(entry, _)| {
+            DateTime::parse_from_rfc3339(&entry.timestamp).is_ok_and(|ts| {
+                let ts = ts.with_timezone(&Utc);
+                ts >= block.start_time && ts < block.end_time
+            })
+        })
+        .map(|(_, source)| *source)
+}
+
+/// Parse JSONL usage lines from a single transcript, skipping blank or
+/// unparseable lines and duplicate messages, sorted by timestamp
+pub fn read_usage_entries(reader: impl BufRead) -> Result<Vec<UsageData>> {
+    let mut entries = Vec::new();
+    let mut processed_hashes: HashSet<String> = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line
+            .trim()
+            .is_empty()
         {
             continue;
         }
+        if let Ok(entry) = serde_json::from_str::<UsageData>(&line) {
+            if let Some(hash) = entry.dedup_key()
+                && !processed_hashes.insert(hash)
+            {
+                continue;
+            }
+            entries.push(entry);
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+    });
+    Ok(entries)
+}
 
-        let file = File::open(&session_file)?;
-        let reader = BufReader::with_capacity(BUFREADER_CAPACITY, file);
+/// How many lines of a transcript parse as usage entries (see `validate`)
+#[derive(Debug, Default)]
+pub struct TranscriptStats {
+    /// Non-blank lines
+    pub lines: usize,
+    /// Lines that parse as a `UsageData` entry, duplicates included
+    pub parsed: usize,
+    /// Parsed entries carrying `message.usage`
+    pub with_usage: usize,
+    /// Usage of the last parsed entry that has any
+    pub last_usage: Option<UsageTokens>,
+}
+
+impl TranscriptStats {
+    pub fn without_usage(&self) -> usize {
+        self.parsed - self.with_usage
+    }
+}
 
-        for line in reader.lines() {
-            let line = line?;
-            if line
-                .trim()
-                .is_empty()
+/// Count the lines of a JSONL transcript that parse like `read_usage_entries`
+/// would read them
+pub fn transcript_stats(reader: impl BufRead) -> Result<TranscriptStats> {
+    let mut stats = TranscriptStats::default();
+    for line in reader.lines() {
+        let line = line?;
+        if line
+            .trim()
+            .is_empty()
+        {
+            continue;
+        }
+        stats.lines += 1;
+        if let Ok(entry) = serde_json::from_str::<UsageData>(&line) {
+            stats.parsed += 1;
+            if let Some(usage) = entry
+                .message
+                .usage
             {
-                continue;
+                stats.with_usage += 1;
+                stats.last_usage = Some(usage);
             }
-            if let Ok(entry) = serde_json::from_str::<UsageData>(&line) {
-                if let (Some(msg_id), Some(req_id)) = (
-                    &entry
-                        .message
-                        .id,
-                    &entry.request_id,
-                ) {
-                    let mut hash = String::with_capacity(msg_id.len() + req_id.len() + 1);
-                    hash.push_str(msg_id);
-                    hash.push(':');
-                    hash.push_str(req_id);
-
-                    if !processed_hashes.insert(hash) {
-                        continue;
-                    }
+        }
+    }
+    Ok(stats)
+}
+
+/// Parse the transcript at `path`; `None` when it cannot be read
+pub fn read_transcript(path: &Path) -> Option<Transcript> {
+    let file = File::open(path).ok()?;
+    let entries = read_usage_entries(BufReader::with_capacity(BUFREADER_CAPACITY, file)).ok()?;
+    Some(Transcript {
+        path: path.to_path_buf(),
+        entries,
+    })
+}
+
+/// The most recent block of `entries`, judged as of the newest entry rather
+/// than the wall clock so piped transcripts render the same on every run
+pub fn latest_block_from_entries(
+    entries: &[UsageData],
+    pricing: &PricingFetcher,
+    block_duration_hours: i64,
+) -> Result<Block> {
+    let newest = entries
+        .last()
+        .and_then(|e| DateTime::parse_from_rfc3339(&e.timestamp).ok())
+        .map_or_else(Utc::now, |ts| ts.with_timezone(&Utc));
+
+    let blocks = group_into_blocks_at(entries, pricing, block_duration_hours, newest)?;
+    match blocks.last() {
+        Some(block) => {
+            let mut block = block.clone();
+            // A transcript is a single source
+            block.session_count = count_block_sessions(&block, entries, std::iter::repeat(0));
+            Ok(block)
+        }
+        None => Ok(Block::inactive(newest, block_duration_hours)),
+    }
+}
+
+/// Block active at `now`, with `overlap` deciding between several. Failing
+/// that, a block that ended less than `grace` ago is returned as active and
+/// flagged `in_grace`.
+fn select_active_block(
+    blocks: &[Block],
+    now: DateTime<Utc>,
+    grace: Duration,
+    overlap: OverlapPolicy,
+) -> Option<Block> {
+    let active: Vec<&Block> = blocks
+        .iter()
+        .filter(|b| b.is_active && b.end_time > now)
+        .collect();
+    if let Some(block) = resolve_overlap(&active, now, overlap) {
+        return Some(block);
+    }
+
+    let last = blocks.last()?;
+    if last.end_time > now || now - last.end_time >= grace {
+        return None;
+    }
+    let mut block = last.clone();
+    block.is_active = true;
+    block.in_grace = true;
+    block.hours_remaining = Some(0.0);
+    Some(block)
+}
+
+/// Deduplicated entries from the JSONL files modified since
+/// `file_cutoff_timestamp`, sorted by timestamp. Each entry comes with the
+/// index of its file in the returned file list. With `max_files`, only the
+/// most recently modified files are read. A preloaded `transcript` is reused
+/// instead of being read again. With `threads` above 1, files are parsed in
+/// parallel; the result is the same as a serial scan.
+fn scan_entries(
+    claude_paths: &[PathBuf],
+    file_cutoff_timestamp: i64,
+    max_files: Option<usize>,
+    threads: usize,
+    transcript: Option<&Transcript>,
+) -> Result<(Vec<UsageData>, Vec<usize>, Vec<PathBuf>)> {
+    let mut all_entries = Vec::with_capacity(1000);
+    let mut processed_hashes: HashSet<String> = HashSet::with_capacity(1000);
+
+    let mut session_files = iter_jsonl_files_since(claude_paths, Some(file_cutoff_timestamp))?;
+    if let Some(max_files) = max_files
+        && session_files.len() > max_files
+    {
+        session_files.sort_by_cached_key(|path| {
+            std::cmp::Reverse(
+                fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .ok(),
+            )
+        });
+        session_files.truncate(max_files);
+    }
+
+    let mut push_entry = |entry: UsageData, source: usize| {
+        if let Some(hash) = entry.dedup_key()
+            && !processed_hashes.insert(hash)
+        {
+            return;
+        }
+        all_entries.push((entry, source));
+    };
+
+    if threads <= 1 {
+        for (source, session_file) in session_files
+            .iter()
+            .enumerate()
+        {
+            if let Some(transcript) = transcript
+                && transcript.path == *session_file
+            {
+                for entry in &transcript.entries {
+                    push_entry(entry.clone(), source);
                 }
+                continue;
+            }
+            if modified_before(session_file, file_cutoff_timestamp) {
+                continue;
+            }
 
-                all_entries.push(entry);
+            let file = File::open(session_file)?;
+            let reader = BufReader::with_capacity(BUFREADER_CAPACITY, file);
+            for line in reader.lines() {
+                let line = line?;
+                if line
+                    .trim()
+                    .is_empty()
+                {
+                    continue;
+                }
+                if let Ok(entry) = serde_json::from_str::<UsageData>(&line) {
+                    push_entry(entry, source);
+                }
+            }
+        }
+    } else {
+        let preloaded = transcript.map(|t| {
+            t.path
+                .as_path()
+        });
+        let parsed = read_session_files(&session_files, file_cutoff_timestamp, threads, preloaded);
+        // Merge in file order so duplicates resolve the same way as in a
+        // serial scan
+        for (source, (session_file, entries)) in session_files
+            .iter()
+            .zip(parsed)
+            .enumerate()
+        {
+            let entries = match transcript {
+                Some(transcript) if transcript.path == *session_file => transcript
+                    .entries
+                    .clone(),
+                _ => match entries? {
+                    Some(entries) => entries,
+                    None => continue,
+                },
+            };
+            for entry in entries {
+                push_entry(entry, source);
             }
         }
     }
 
-    all_entries.sort_by(|a, b| {
+    all_entries.sort_by(|(a, _), (b, _)| {
         a.timestamp
             .cmp(&b.timestamp)
     });
+    let (all_entries, sources): (Vec<UsageData>, Vec<usize>) = all_entries
+        .into_iter()
+        .unzip();
+    Ok((all_entries, sources, session_files))
+}
 
-    let blocks = group_into_blocks(&all_entries, pricing)?;
+/// Entries of each of `session_files`, in the same order, spread over
+/// `threads` scoped threads. `skip` (a preloaded transcript) is not read.
+fn read_session_files(
+    session_files: &[PathBuf],
+    file_cutoff_timestamp: i64,
+    threads: usize,
+    skip: Option<&Path>,
+) -> Vec<Result<Option<Vec<UsageData>>>> {
+    let read = |path: &PathBuf| {
+        if skip == Some(path.as_path()) {
+            return Ok(None);
+        }
+        read_session_file(path, file_cutoff_timestamp)
+    };
+    if session_files.len() < 2 {
+        return session_files
+            .iter()
+            .map(read)
+            .collect();
+    }
 
-    let now = Utc::now();
-    for block in blocks
+    // Contiguous chunks keep results in file order once joined
+    let chunk_size = session_files
+        .len()
+        .div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = session_files
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(read)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// Whether `session_file` was last modified before `file_cutoff_timestamp`,
+/// i.e. outside the lookback window
+fn modified_before(session_file: &Path, file_cutoff_timestamp: i64) -> bool {
+    fs::metadata(session_file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| {
+            modified
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()
+        })
+        .is_some_and(|modified| (modified.as_secs() as i64) < file_cutoff_timestamp)
+}
+
+/// Parsed entries of one session file, duplicates included; `None` when it
+/// was not modified since `file_cutoff_timestamp`
+fn read_session_file(
+    session_file: &Path,
+    file_cutoff_timestamp: i64,
+) -> Result<Option<Vec<UsageData>>> {
+    if modified_before(session_file, file_cutoff_timestamp) {
+        return Ok(None);
+    }
+
+    let file = File::open(session_file)?;
+    let reader = BufReader::with_capacity(BUFREADER_CAPACITY, file);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line
+            .trim()
+            .is_empty()
+        {
+            continue;
+        }
+        if let Ok(entry) = serde_json::from_str::<UsageData>(&line) {
+            entries.push(entry);
+        }
+    }
+    Ok(Some(entries))
+}
+
+/// Cost of every entry of one session's transcript, regardless of block
+pub fn session_cost(entries: &[UsageData], pricing: &PricingFetcher) -> f64 {
+    entries
         .iter()
-        .rev()
-    {
-        if block.is_active && block.end_time > now {
-            return Ok(block.clone());
+        .map(|entry| pricing.calculate_entry_cost(entry))
+        .sum()
+}
+
+/// Every block with entries from the trailing `window`, oldest first
+pub fn blocks_since(
+    claude_paths: &[PathBuf],
+    pricing: &PricingFetcher,
+    block_duration_hours: i64,
+    window: Duration,
+) -> Result<Vec<Block>> {
+    let since = Utc::now() - window;
+    let (entries, _, _) = scan_entries(claude_paths, since.timestamp(), None, 1, None)?;
+    // Files touched recently can still hold older entries
+    let entries: Vec<UsageData> = entries
+        .into_iter()
+        .filter(|entry| DateTime::parse_from_rfc3339(&entry.timestamp).is_ok_and(|ts| ts >= since))
+        .collect();
+    group_into_blocks(&entries, pricing, block_duration_hours)
+}
+
+/// Pick one of the `active` blocks according to `policy`
+fn resolve_overlap(active: &[&Block], now: DateTime<Utc>, policy: OverlapPolicy) -> Option<Block> {
+    let chosen = match policy {
+        OverlapPolicy::MostRecentStart => active
+            .iter()
+            .max_by_key(|b| b.start_time),
+        OverlapPolicy::HighestCost => active
+            .iter()
+            .max_by(|a, b| {
+                a.cost_usd
+                    .total_cmp(&b.cost_usd)
+            }),
+        OverlapPolicy::Merge => return merge_blocks(active, now),
+    };
+    chosen.map(|b| (*b).clone())
+}
+
+/// One block spanning all of `blocks`, with their costs and tokens summed
+fn merge_blocks(blocks: &[&Block], now: DateTime<Utc>) -> Option<Block> {
+    let (first, rest) = blocks.split_first()?;
+    let mut merged = (*first).clone();
+    for block in rest {
+        merged.start_time = merged
+            .start_time
+            .min(block.start_time);
+        merged.end_time = merged
+            .end_time
+            .max(block.end_time);
+        merged.cost_usd += block.cost_usd;
+        merged.total_tokens += block.total_tokens;
+        merged.cache_creation_tokens += block.cache_creation_tokens;
+        merged.cache_read_tokens += block.cache_read_tokens;
+        merged
+            .entry_costs
+            .extend_from_slice(&block.entry_costs);
+    }
+    merged
+        .entry_costs
+        .sort_by_key(|(ts, _)| *ts);
+    merged.hours_remaining = Some(((merged.end_time - now).num_seconds() as f64 / 3600.0).max(0.0));
+    Some(merged)
+}
+
+/// How `find_active_block` scans transcripts and picks the active block
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions {
+    pub block_duration_hours: i64,
+    /// Flag the block `stale` when the newest entry is older than this
+    pub stale_after_minutes: Option<i64>,
+    /// A block that ended less than this long ago still counts as active
+    pub grace_minutes: i64,
+    /// Tie-break between several active blocks
+    pub overlap: OverlapPolicy,
+    /// Read only the newest N candidate files (all when unset)
+    pub max_files: Option<usize>,
+    /// Threads parsing files; 1 reads them serially
+    pub threads: usize,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            block_duration_hours: 5,
+            stale_after_minutes: None,
+            grace_minutes: 0,
+            overlap: OverlapPolicy::default(),
+            max_files: None,
+            threads: 1,
         }
     }
+}
 
-    let next_end = now + Duration::hours(BLOCK_DURATION_HOURS);
-    Ok(Block {
-        start_time: now,
-        end_time: next_end,
-        cost_usd: 0.0,
-        is_active: false,
-        hours_remaining: None,
-    })
+/// Find active billing block
+pub fn find_active_block(
+    claude_paths: &[PathBuf],
+    pricing: &PricingFetcher,
+    options: &ScanOptions,
+) -> Result<Block> {
+    find_active_block_with(claude_paths, pricing, options, None)
+}
+
+/// Like `find_active_block`, reusing the already parsed `transcript` instead
+/// of reading that file again during the scan
+pub fn find_active_block_with(
+    claude_paths: &[PathBuf],
+    pricing: &PricingFetcher,
+    options: &ScanOptions,
+    transcript: Option<&Transcript>,
+) -> Result<Block> {
+    let ScanOptions {
+        block_duration_hours,
+        stale_after_minutes,
+        grace_minutes,
+        overlap,
+        max_files,
+        threads,
+    } = *options;
+    let now = Utc::now();
+    let file_cutoff_time = now - Duration::hours(block_duration_hours + FILE_LOOKBACK_BUFFER_HOURS);
+    let (all_entries, sources, session_files) = scan_entries(
+        claude_paths,
+        file_cutoff_time.timestamp(),
+        max_files,
+        threads,
+        transcript,
+    )?;
+
+    // Blocks ignore entries without usage; they still count towards sessions
+    // and staleness
+    let blocks = group_into_blocks(&all_entries, pricing, block_duration_hours)?;
+
+    let stale = match (stale_after_minutes, all_entries.last()) {
+        (Some(minutes), Some(newest)) => DateTime::parse_from_rfc3339(&newest.timestamp)
+            .is_ok_and(|ts| now - ts.with_timezone(&Utc) > Duration::minutes(minutes)),
+        _ => false,
+    };
+    match select_active_block(&blocks, now, Duration::minutes(grace_minutes), overlap) {
+        Some(mut block) => {
+            block.session_count = count_block_sessions(
+                &block,
+                &all_entries,
+                sources
+                    .iter()
+                    .copied(),
+            );
+            block.latest_source = latest_block_source(&block, &all_entries, &sources)
+                .map(|source| session_files[source].clone());
+            block.stale = stale;
+            Ok(block)
+        }
+        None => Ok(Block::inactive(now, block_duration_hours)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn entry(timestamp: &str) -> UsageData {
+        serde_json::from_str(&format!(
+            r#"{{"timestamp":"{}","message":{{"usage":{{"input_tokens":10,"output_tokens":10}}}}}}"#,
+            timestamp
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_session_cost() {
+        let path = std::env::temp_dir().join("ccusage-test-session-cost.jsonl");
+        let line = |id: &str, timestamp: &str| {
+            format!(
+                r#"{{"timestamp":"{}","requestId":"req_{}","message":{{"id":"msg_{}","usage":{{"input_tokens":100000,"output_tokens":0}}}}}}"#,
+                timestamp, id, id
+            )
+        };
+        let transcript = [
+            line("1", "2025-01-01T10:00:00Z"),
+            r#"{"timestamp":"2025-01-01T10:01:00Z","message":{"role":"user","content":"hi"}}"#
+                .to_string(),
+            // Spans two blocks; both count towards the session
+            line("2", "2025-01-01T18:00:00Z"),
+            line("2", "2025-01-01T18:00:00Z"),
+        ]
+        .join("\n");
+        fs::write(&path, transcript).unwrap();
+        let entries = read_transcript(&path)
+            .unwrap()
+            .entries;
+        fs::remove_file(&path).unwrap();
+
+        // Fallback Sonnet 4 pricing: $3/M input tokens, 100k per entry
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        assert!((session_cost(&entries, &pricing) - 0.6).abs() < 1e-9);
+        assert_eq!(session_cost(&[], &pricing), 0.0);
+    }
+
+    #[test]
+    fn test_transcript_stats() {
+        let transcript = [
+            r#"{"timestamp":"2025-01-01T10:00:00Z","message":{"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"timestamp":"2025-01-01T10:01:00Z","message":{"role":"user","content":"hi"}}"#,
+            "",
+            r#"{"type":"summary","summary":"no timestamp"}"#,
+            "not json",
+            r#"{"timestamp":"2025-01-01T10:02:00Z","message":{"usage":{"input_tokens":20,"output_tokens":7,"cache_read_input_tokens":300}}}"#,
+        ]
+        .join("\n");
+
+        let stats = transcript_stats(transcript.as_bytes()).unwrap();
+        assert_eq!(stats.lines, 5);
+        assert_eq!(stats.parsed, 3);
+        assert_eq!(stats.with_usage, 2);
+        assert_eq!(stats.without_usage(), 1);
+        let last = stats
+            .last_usage
+            .unwrap();
+        assert_eq!(last.input_tokens, 20);
+        assert_eq!(last.cache_read_input_tokens, 300);
+
+        let empty = transcript_stats("not json\n".as_bytes()).unwrap();
+        assert_eq!(empty.parsed, 0);
+        assert!(
+            empty
+                .last_usage
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_entries_with_and_without_usage() {
+        let recent = Utc::now() - Duration::minutes(30);
+        let earlier = recent - Duration::hours(2);
+        let user_line: UsageData = serde_json::from_str(&format!(
+            r#"{{"timestamp":"{}","message":{{"role":"user","content":"hi"}}}}"#,
+            earlier.to_rfc3339()
+        ))
+        .unwrap();
+        assert!(!user_line.has_usage());
+        assert_eq!(
+            user_line
+                .usage()
+                .input_tokens,
+            0
+        );
+
+        let assistant = entry(&recent.to_rfc3339());
+        assert!(assistant.has_usage());
+        assert_eq!(
+            assistant
+                .usage()
+                .output_tokens,
+            10
+        );
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        assert_eq!(pricing.calculate_entry_cost(&user_line), 0.0);
+
+        let blocks = group_into_blocks(&[user_line, assistant], &pricing, 5).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].start_time, floor_to_hour(recent));
+        assert_eq!(blocks[0].total_tokens, 20);
+    }
+
+    #[test]
+    fn test_find_active_block_counts_sessions() {
+        let base = std::env::temp_dir().join("ccusage-test-sessions");
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+
+        let recent = (Utc::now() - Duration::minutes(30)).to_rfc3339();
+        let old = (Utc::now() - Duration::hours(10)).to_rfc3339();
+        let line = |ts: &str, id: &str| {
+            format!(
+                r#"{{"timestamp":"{}","requestId":"{}","message":{{"id":"{}","usage":{{"input_tokens":10,"output_tokens":10}}}}}}"#,
+                ts, id, id
+            )
+        };
+        fs::write(
+            project.join("a.jsonl"),
+            format!("{}\n{}\n", line(&recent, "a1"), line(&recent, "a2")),
+        )
+        .unwrap();
+        fs::write(project.join("b.jsonl"), line(&recent, "b1")).unwrap();
+        fs::write(project.join("c.jsonl"), line(&old, "c1")).unwrap();
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let block = find_active_block(
+            std::slice::from_ref(&base),
+            &pricing,
+            &ScanOptions::default(),
+        )
+        .unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(block.is_active);
+        assert_eq!(block.session_count, 2);
+    }
+
+    #[test]
+    fn test_find_active_block_latest_source() {
+        let base = std::env::temp_dir().join("ccusage-test-latest-source");
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+
+        let recent = (Utc::now() - Duration::minutes(30)).to_rfc3339();
+        let newest = (Utc::now() - Duration::minutes(20)).to_rfc3339();
+        let line = |ts: &str, id: &str| {
+            format!(
+                r#"{{"timestamp":"{}","requestId":"{}","message":{{"id":"{}","usage":{{"input_tokens":10,"output_tokens":10}}}}}}"#,
+                ts, id, id
+            )
+        };
+        fs::write(project.join("a.jsonl"), line(&recent, "a1")).unwrap();
+        fs::write(project.join("b.jsonl"), line(&newest, "b1")).unwrap();
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let block = find_active_block(
+            std::slice::from_ref(&base),
+            &pricing,
+            &ScanOptions::default(),
+        )
+        .unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(block.latest_source, Some(project.join("b.jsonl")));
+    }
+
+    #[test]
+    fn test_find_active_block_flags_stale_data() {
+        let base = std::env::temp_dir().join("ccusage-test-stale");
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+
+        let old = (Utc::now() - Duration::hours(2)).to_rfc3339();
+        fs::write(
+            project.join("a.jsonl"),
+            format!(
+                r#"{{"timestamp":"{}","message":{{"usage":{{"input_tokens":10,"output_tokens":10}}}}}}"#,
+                old
+            ),
+        )
+        .unwrap();
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let paths = std::slice::from_ref(&base);
+        let stale = find_active_block(
+            paths,
+            &pricing,
+            &ScanOptions {
+                stale_after_minutes: Some(30),
+                ..ScanOptions::default()
+            },
+        )
+        .unwrap();
+        let fresh = find_active_block(
+            paths,
+            &pricing,
+            &ScanOptions {
+                stale_after_minutes: Some(180),
+                ..ScanOptions::default()
+            },
+        )
+        .unwrap();
+        let disabled = find_active_block(paths, &pricing, &ScanOptions::default()).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(stale.is_active);
+        assert!(stale.stale);
+        assert!(!fresh.stale);
+        assert!(!disabled.stale);
+    }
+
+    #[test]
+    fn test_parallel_scan_matches_serial() {
+        let base = std::env::temp_dir().join("ccusage-test-parallel-scan");
+        let recent = Utc::now() - Duration::hours(1);
+        for i in 0..7i64 {
+            let project = base.join(format!("project-{}", i % 3));
+            fs::create_dir_all(&project).unwrap();
+            let lines: Vec<String> = (0..20i64)
+                .map(|n| {
+                    // Every file repeats message m0 so dedup order matters
+                    let id = if n == 0 { 0 } else { i * 100 + n };
+                    format!(
+                        r#"{{"timestamp":"{}","requestId":"r{}","message":{{"id":"m{}","usage":{{"input_tokens":{},"output_tokens":1}}}}}}"#,
+                        (recent + Duration::seconds(n * 7 + i)).to_rfc3339(),
+                        id,
+                        id,
+                        i * 1000 + n
+                    )
+                })
+                .collect();
+            fs::write(project.join(format!("{}.jsonl", i)), lines.join("\n")).unwrap();
+        }
+
+        let paths = std::slice::from_ref(&base);
+        let cutoff = (Utc::now() - Duration::hours(12)).timestamp();
+        let summarize = |threads| {
+            let (entries, sources, files) =
+                scan_entries(paths, cutoff, None, threads, None).unwrap();
+            let entries: Vec<(String, Option<String>, u64)> = entries
+                .into_iter()
+                .map(|entry| {
+                    let tokens = entry
+                        .message
+                        .usage
+                        .as_ref()
+                        .map_or(0, |usage| usage.input_tokens);
+                    (
+                        entry
+                            .timestamp
+                            .clone(),
+                        entry.dedup_key(),
+                        tokens,
+                    )
+                })
+                .collect();
+            (entries, sources, files)
+        };
+        let serial = summarize(1);
+        let parallel = summarize(4);
+        let oversubscribed = summarize(64);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(
+            serial
+                .0
+                .len(),
+            7 * 19 + 1
+        );
+        assert_eq!(serial, parallel);
+        assert_eq!(serial, oversubscribed);
+    }
+
+    #[test]
+    fn test_max_scan_files_reads_newest() {
+        let base = std::env::temp_dir().join("ccusage-test-max-scan");
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+
+        let recent = (Utc::now() - Duration::minutes(30)).to_rfc3339();
+        for i in 0..5u64 {
+            let path = project.join(format!("{}.jsonl", i));
+            fs::write(
+                &path,
+                format!(
+                    r#"{{"timestamp":"{}","requestId":"r{}","message":{{"id":"m{}","usage":{{"input_tokens":{},"output_tokens":0}}}}}}"#,
+                    recent,
+                    i,
+                    i,
+                    10u64.pow(i as u32)
+                ),
+            )
+            .unwrap();
+            // File i is i minutes newer than file 0
+            let modified =
+                std::time::SystemTime::now() - std::time::Duration::from_secs(600 - i * 60);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let paths = std::slice::from_ref(&base);
+        let capped = find_active_block(
+            paths,
+            &pricing,
+            &ScanOptions {
+                max_files: Some(2),
+                ..ScanOptions::default()
+            },
+        )
+        .unwrap();
+        let all = find_active_block(paths, &pricing, &ScanOptions::default()).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        // Only files 3 and 4 were read
+        assert_eq!(capped.session_count, 2);
+        assert_eq!(capped.total_tokens, 11_000);
+        assert_eq!(all.session_count, 5);
+        assert_eq!(all.total_tokens, 11_111);
+    }
+
+    #[test]
+    fn test_transcript_is_read_once() {
+        let base = std::env::temp_dir().join("ccusage-test-transcript-once");
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+
+        let recent = (Utc::now() - Duration::minutes(10)).to_rfc3339();
+        let line = |tokens: u64| {
+            format!(
+                r#"{{"timestamp":"{}","requestId":"r1","message":{{"id":"m1","usage":{{"input_tokens":{},"output_tokens":0}}}}}}"#,
+                recent, tokens
+            )
+        };
+        let path = project.join("a.jsonl");
+        fs::write(&path, line(10)).unwrap();
+        let transcript = read_transcript(&path).unwrap();
+        // Rewritten after the single read: only a second open would see it
+        fs::write(&path, line(99)).unwrap();
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let paths = std::slice::from_ref(&base);
+        let shared =
+            find_active_block_with(paths, &pricing, &ScanOptions::default(), Some(&transcript))
+                .unwrap();
+        let reread = find_active_block(paths, &pricing, &ScanOptions::default()).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(
+            transcript
+                .entries
+                .len(),
+            1
+        );
+        assert_eq!(shared.total_tokens, 10);
+        assert_eq!(shared.latest_source, Some(path));
+        assert_eq!(reread.total_tokens, 99);
+    }
+
+    #[test]
+    fn test_expired_block_within_grace() {
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let entries = vec![entry("2025-01-01T10:15:00Z")];
+        // The 10:00-15:00 block ended two minutes ago
+        let now = "2025-01-01T15:02:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let blocks = group_into_blocks_at(&entries, &pricing, 5, now).unwrap();
+        assert!(!blocks[0].is_active);
+
+        let kept =
+            select_active_block(&blocks, now, Duration::minutes(5), OverlapPolicy::default())
+                .unwrap();
+        assert!(kept.is_active);
+        assert!(kept.in_grace);
+        assert_eq!(kept.hours_remaining, Some(0.0));
+        assert_eq!(kept.total_tokens, 20);
+
+        assert!(
+            select_active_block(&blocks, now, Duration::zero(), OverlapPolicy::default()).is_none()
+        );
+        assert!(
+            select_active_block(&blocks, now, Duration::minutes(1), OverlapPolicy::default())
+                .is_none()
+        );
+
+        // A block that is still running is never flagged
+        let running = "2025-01-01T14:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let blocks = group_into_blocks_at(&entries, &pricing, 5, running).unwrap();
+        let active = select_active_block(
+            &blocks,
+            running,
+            Duration::minutes(5),
+            OverlapPolicy::default(),
+        )
+        .unwrap();
+        assert!(!active.in_grace);
+    }
+
+    #[test]
+    fn test_overlapping_active_blocks() {
+        let now = Utc::now();
+        let block = |start_minutes_ago: i64, cost_usd: f64, total_tokens: u64| {
+            let mut block = Block::inactive(now - Duration::minutes(start_minutes_ago), 5);
+            block.is_active = true;
+            block.cost_usd = cost_usd;
+            block.total_tokens = total_tokens;
+            block
+        };
+        // The older block is the more expensive one
+        let blocks = vec![block(120, 8.0, 800), block(30, 2.0, 200)];
+        let select = |policy| select_active_block(&blocks, now, Duration::zero(), policy).unwrap();
+
+        let recent = select(OverlapPolicy::MostRecentStart);
+        assert_eq!(recent.start_time, blocks[1].start_time);
+        assert_eq!(recent.cost_usd, 2.0);
+
+        let costly = select(OverlapPolicy::HighestCost);
+        assert_eq!(costly.start_time, blocks[0].start_time);
+        assert_eq!(costly.cost_usd, 8.0);
+
+        let merged = select(OverlapPolicy::Merge);
+        assert_eq!(merged.start_time, blocks[0].start_time);
+        assert_eq!(merged.end_time, blocks[1].end_time);
+        assert_eq!(merged.cost_usd, 10.0);
+        assert_eq!(merged.total_tokens, 1000);
+        assert!(
+            (merged
+                .hours_remaining
+                .unwrap()
+                - 4.5)
+                .abs()
+                < 0.01
+        );
+    }
+
+    #[test]
+    fn test_block_cache_token_totals() {
+        let cached: UsageData = serde_json::from_str(
+            r#"{"timestamp":"2025-01-01T10:30:00Z","message":{"usage":{"input_tokens":5,"output_tokens":15,"cache_creation_input_tokens":100,"cache_read_input_tokens":1000}}}"#,
+        )
+        .unwrap();
+        let entries = vec![entry("2025-01-01T10:15:00Z"), cached];
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let blocks = group_into_blocks(&entries, &pricing, 5).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].cache_creation_tokens, 100);
+        assert_eq!(blocks[0].cache_read_tokens, 1000);
+        assert_eq!(blocks[0].total_tokens, 1140);
+        assert_eq!(blocks[0].display_tokens(true), 1140);
+        assert_eq!(blocks[0].display_tokens(false), 40);
+    }
+
+    #[test]
+    fn test_group_into_blocks_custom_duration() {
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let entries = vec![
+            entry("2025-01-01T10:15:00Z"),
+            entry("2025-01-01T13:00:00Z"),
+            entry("2025-01-01T14:30:00Z"),
+        ];
+
+        let blocks = group_into_blocks(&entries, &pricing, 5).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].total_tokens, 60);
+
+        let blocks = group_into_blocks(&entries, &pricing, 4).unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            blocks[0].start_time,
+            "2025-01-01T10:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        );
+        assert_eq!(
+            blocks[0].end_time,
+            "2025-01-01T14:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        );
+        assert_eq!(
+            blocks[1].start_time,
+            "2025-01-01T14:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_latest_block_from_piped_lines() {
+        let input = [
+            r#"{"timestamp":"2025-01-01T14:30:00Z","message":{"id":"b","usage":{"input_tokens":10,"output_tokens":10}},"requestId":"r2"}"#,
+            "",
+            "not json",
+            r#"{"timestamp":"2025-01-01T10:15:00Z","message":{"id":"a","usage":{"input_tokens":10,"output_tokens":10}},"requestId":"r1"}"#,
+            r#"{"timestamp":"2025-01-01T14:30:00Z","message":{"id":"b","usage":{"input_tokens":10,"output_tokens":10}},"requestId":"r2"}"#,
+        ]
+        .join("\n");
+
+        let entries = read_usage_entries(input.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, "2025-01-01T10:15:00Z");
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let block = latest_block_from_entries(&entries, &pricing, 5).unwrap();
+        assert!(block.is_active);
+        assert_eq!(block.total_tokens, 40);
+        assert_eq!(block.session_count, 1);
+        assert_eq!(
+            block.start_time,
+            "2025-01-01T10:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        );
+
+        let empty = latest_block_from_entries(&[], &pricing, 5).unwrap();
+        assert!(!empty.is_active);
+    }
 }
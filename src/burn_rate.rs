@@ -1,11 +1,16 @@
 use crate::types::{ApiUsageData, Block, BurnRate, LimitType};
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 
+/// Block spend per hour and pace against the 5h/7d limits. The rate is
+/// cost-based, so cache reads and writes already count at their own prices;
+/// there is no separate token rate to adjust. With `window_minutes`, only
+/// the spend in that trailing window counts.
 pub fn calculate_burn_rate(
     block: &Block,
     api_usage: Option<&ApiUsageData>,
     burn_rate_show_ratio: f64,
+    window_minutes: Option<u64>,
 ) -> Result<BurnRate> {
     if !block.is_active {
         return Ok(BurnRate::default());
@@ -18,7 +23,10 @@ pub fn calculate_burn_rate(
         return Ok(BurnRate::default());
     }
 
-    let cost_per_hour = (block.cost_usd / elapsed) * 60.0;
+    let cost_per_hour = match window_minutes {
+        Some(window) => windowed_cost_per_hour(block, now, window, elapsed),
+        None => (block.cost_usd / elapsed) * 60.0,
+    };
 
     let api_usage = match api_usage {
         Some(api) => api,
@@ -71,10 +79,11 @@ pub fn calculate_burn_rate(
     };
 
     let is_at_limit = api_usage.five_hour_percent >= 100.0 || api_usage.seven_day_percent >= 100.0;
-    let reset_in = reset_at.map(|reset| reset - now);
+    // A reset already in the past (clock skew, stale cache) counts as now
+    let reset_in = reset_at.map(|reset| (reset - now).max(Duration::zero()));
     let seven_day_reset_in = api_usage
         .seven_day_resets_at
-        .map(|reset| reset - now);
+        .map(|reset| (reset - now).max(Duration::zero()));
 
     Ok(BurnRate {
         cost_per_hour,
@@ -87,6 +96,26 @@ pub fn calculate_burn_rate(
     })
 }
 
+/// Spend per hour over the trailing `window` minutes, capped to the elapsed
+/// block. Blocks without per-entry costs fall back to the whole block.
+fn windowed_cost_per_hour(block: &Block, now: DateTime<Utc>, window: u64, elapsed: f64) -> f64 {
+    if block
+        .entry_costs
+        .is_empty()
+    {
+        return (block.cost_usd / elapsed) * 60.0;
+    }
+
+    let since = now - Duration::minutes(window as i64);
+    let recent_cost: f64 = block
+        .entry_costs
+        .iter()
+        .filter(|(ts, _)| *ts >= since)
+        .map(|(_, cost)| cost)
+        .sum();
+    (recent_cost / elapsed.min(window as f64)) * 60.0
+}
+
 fn calculate_limit_ratio(
     current_percent: f64,
     resets_at: Option<DateTime<Utc>>,
@@ -123,3 +152,32 @@ fn calculate_limit_ratio(
         0.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burn_rate_window() {
+        let now = Utc::now();
+        let mut block = Block::inactive(now - Duration::minutes(120), 5);
+        block.is_active = true;
+        block.cost_usd = 11.0;
+        block.entry_costs = vec![
+            (now - Duration::minutes(100), 10.0),
+            (now - Duration::minutes(10), 1.0),
+        ];
+
+        // Whole block: $11 over two hours
+        let whole = calculate_burn_rate(&block, None, 0.8, None).unwrap();
+        assert!((whole.cost_per_hour - 5.5).abs() < 0.1);
+
+        // The old burst falls outside a 30-minute window: $1 over 30 minutes
+        let recent = calculate_burn_rate(&block, None, 0.8, Some(30)).unwrap();
+        assert!((recent.cost_per_hour - 2.0).abs() < 0.01);
+
+        // A window longer than the block only spans the elapsed time
+        let wide = calculate_burn_rate(&block, None, 0.8, Some(600)).unwrap();
+        assert!((wide.cost_per_hour - whole.cost_per_hour).abs() < 0.1);
+    }
+}
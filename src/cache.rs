@@ -1,16 +1,53 @@
-use crate::types::Semaphore;
+use crate::alert;
+use crate::config::CacheSettings;
+use crate::types::{RenderHistory, RenderedStatusline, Semaphore};
 use anyhow::Result;
 use chrono::Utc;
 use fs2::FileExt;
+use std::ffi::OsString;
 use std::fs::{self, File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Upper bound on `min_render_interval_ms` so bursts never starve real updates
+const MAX_RENDER_INTERVAL_MS: u64 = 2000;
+
+static FORCE_REFRESH: OnceLock<bool> = OnceLock::new();
+
+/// Ignore every cache for this process (from `--refresh`): the output,
+/// usage API, pricing and update-check caches are refetched and rewritten
+pub fn set_force_refresh(enabled: bool) {
+    let _ = FORCE_REFRESH.set(enabled);
+}
+
+/// Whether cached data must be refetched (see `set_force_refresh`)
+pub fn force_refresh() -> bool {
+    FORCE_REFRESH
+        .get()
+        .copied()
+        .unwrap_or(false)
+}
 
 /// Get cache directory from XDG_RUNTIME_DIR, scoped per config dir.
 /// Fallback on Unix is per-user `/run/user/<uid>` (mode 0700, tmpfs); on
-/// non-Unix targets it is `std::env::temp_dir()`.
+/// non-Unix targets it is `std::env::temp_dir()`. If the runtime dir is not
+/// writable, falls back to `$TMPDIR` and then `std::env::temp_dir()`.
 pub fn get_cache_dir() -> Result<PathBuf> {
-    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+    let config_dir = crate::paths::claude_config_dir()?;
+    let config_name = crate::paths::cache_scope_name(&config_dir, crate::paths::profile());
+    let candidates = cache_dir_bases(
+        std::env::var_os("XDG_RUNTIME_DIR"),
+        std::env::var_os("TMPDIR"),
+    );
+
+    first_writable_cache_dir(&candidates, &config_name)
+}
+
+/// Base directories to try for the cache, given `XDG_RUNTIME_DIR` and
+/// `TMPDIR`, in order of preference
+fn cache_dir_bases(runtime_dir: Option<OsString>, tmpdir: Option<OsString>) -> Vec<PathBuf> {
+    let runtime_dir = runtime_dir
         .map(PathBuf::from)
         .unwrap_or_else(|| {
             #[cfg(unix)]
@@ -22,15 +59,69 @@ pub fn get_cache_dir() -> Result<PathBuf> {
                 std::env::temp_dir()
             }
         });
-    let config_dir = crate::paths::claude_config_dir()?;
-    let config_name = config_dir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or(".claude")
-        .trim_start_matches('.');
-    Ok(runtime_dir
-        .join("ccusage-statusline-rs")
-        .join(config_name))
+
+    let mut candidates = vec![runtime_dir];
+    if let Some(tmpdir) = tmpdir {
+        candidates.push(PathBuf::from(tmpdir));
+    }
+    candidates.push(std::env::temp_dir());
+    candidates
+}
+
+/// Suggestion appended to cache directory errors caused by permissions
+const CACHE_DIR_HINT: &str = "set XDG_RUNTIME_DIR or TMPDIR to a directory you can write to";
+
+/// Return the first `<base>/ccusage-statusline-rs/<config_name>` that can be
+/// created and written to
+fn first_writable_cache_dir(bases: &[PathBuf], config_name: &str) -> Result<PathBuf> {
+    let mut failures = Vec::new();
+    for base in bases {
+        let dir = base
+            .join("ccusage-statusline-rs")
+            .join(config_name);
+        match check_writable_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) => failures.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+
+    anyhow::bail!(
+        "No writable cache directory found ({}); {}",
+        failures.join(", "),
+        CACHE_DIR_HINT
+    )
+}
+
+/// Create `dir` if needed and check that files can be created inside it. An
+/// existing directory was checked when it was created, so it is taken as is
+/// to avoid touching the disk on every render.
+fn check_writable_dir(dir: &Path) -> io::Result<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(".writable"))
+        .map(drop)
+}
+
+/// Create the cache directory `dir`, naming it and the OS error on failure.
+/// Permission errors also suggest moving the cache elsewhere.
+pub fn create_cache_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).map_err(|e| {
+        let hint = if e.kind() == io::ErrorKind::PermissionDenied {
+            format!("; {}", CACHE_DIR_HINT)
+        } else {
+            String::new()
+        };
+        anyhow::Error::new(e).context(format!(
+            "Failed to create cache directory {}{}",
+            dir.display(),
+            hint
+        ))
+    })
 }
 
 /// Try to get cached output if valid
@@ -38,6 +129,7 @@ pub fn try_get_cached(
     cache_path: &Path,
     transcript_path: &str,
     ttl_secs: u64,
+    min_render_interval_ms: u64,
 ) -> Result<Option<String>> {
     if !cache_path.exists() {
         return Ok(None);
@@ -62,9 +154,15 @@ pub fn try_get_cached(
     };
 
     // Check if cache is still valid
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
+    let now_duration = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+    let now = now_duration.as_secs();
+
+    // Rendered very recently: reuse output even if the transcript just changed
+    let since_render_ms =
+        (now_duration.as_millis() as u64).saturating_sub(semaphore.last_render_ms);
+    if since_render_ms < min_render_interval_ms.min(MAX_RENDER_INTERVAL_MS) {
+        return Ok(Some(semaphore.last_output));
+    }
 
     let is_expired = now - semaphore.last_update_time >= ttl_secs;
 
@@ -79,8 +177,71 @@ pub fn try_get_cached(
     Ok(Some(semaphore.last_output))
 }
 
+/// Return cached output when valid, otherwise call `render` and cache its
+/// result. `render` is the only place network or transcript-scanning work may
+/// happen, so a cache hit never touches pricing, the API or update checks.
+///
+/// `render` receives the history recorded by the previous render, if any.
+pub fn get_or_render(
+    cache_path: &Path,
+    transcript_path: &str,
+    cache_settings: &CacheSettings,
+    render: impl FnOnce(RenderHistory) -> Result<RenderedStatusline>,
+) -> Result<String> {
+    get_or_render_with(
+        cache_path,
+        transcript_path,
+        cache_settings,
+        force_refresh(),
+        render,
+    )
+}
+
+/// Like `get_or_render`; with `refresh`, always renders and rewrites the cache
+fn get_or_render_with(
+    cache_path: &Path,
+    transcript_path: &str,
+    cache_settings: &CacheSettings,
+    refresh: bool,
+    render: impl FnOnce(RenderHistory) -> Result<RenderedStatusline>,
+) -> Result<String> {
+    if !refresh
+        && let Some(cached) = try_get_cached(
+            cache_path,
+            transcript_path,
+            cache_settings.output_cache_secs,
+            cache_settings.min_render_interval_ms,
+        )?
+    {
+        return Ok(cached);
+    }
+
+    let rendered = render(last_history(cache_path))?;
+    update_cache(
+        cache_path,
+        transcript_path,
+        &rendered.output,
+        rendered.history,
+    )?;
+    Ok(rendered.output)
+}
+
+/// History stored by the last render, empty if there is no readable cache
+fn last_history(cache_path: &Path) -> RenderHistory {
+    fs::read_to_string(cache_path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Semaphore>(&contents).ok())
+        .map(|s| s.history)
+        .unwrap_or_default()
+}
+
 /// Update cache with new output
-pub fn update_cache(cache_path: &Path, transcript_path: &str, output: &str) -> Result<()> {
+pub fn update_cache(
+    cache_path: &Path,
+    transcript_path: &str,
+    output: &str,
+    history: RenderHistory,
+) -> Result<()> {
     let mut file = OpenOptions::new()
         .create(true)
         .write(true)
@@ -90,18 +251,18 @@ pub fn update_cache(cache_path: &Path, transcript_path: &str, output: &str) -> R
     // Acquire exclusive lock
     file.lock_exclusive()?;
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
+    let now_duration = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
 
     let mtime = get_file_mtime(transcript_path)?;
 
     let semaphore = Semaphore {
         date: Utc::now().to_rfc3339(),
         last_output: output.to_string(),
-        last_update_time: now,
+        last_update_time: now_duration.as_secs(),
         transcript_path: transcript_path.to_string(),
         transcript_mtime: mtime,
+        last_render_ms: now_duration.as_millis() as u64,
+        history,
     };
 
     let json = serde_json::to_string(&semaphore)?;
@@ -111,8 +272,9 @@ pub fn update_cache(cache_path: &Path, transcript_path: &str, output: &str) -> R
     Ok(())
 }
 
-/// Remove .lock files whose mtime exceeds `ttl_secs`. Runs at most once
-/// per `ttl_secs`, gated by the mtime of a marker file.
+/// Remove .lock files whose mtime exceeds `ttl_secs`, and cost alert markers
+/// older than `alert::MARKER_TTL_SECS`. Runs at most once per `ttl_secs`,
+/// gated by the mtime of a marker file.
 pub fn cleanup_stale_locks(cache_dir: &Path, ttl_secs: u64) {
     let marker = cache_dir.join("last-cleanup");
     if let Ok(mtime) = fs::metadata(&marker).and_then(|m| m.modified())
@@ -136,13 +298,21 @@ pub fn cleanup_stale_locks(cache_dir: &Path, ttl_secs: u64) {
 
     for entry in entries.flatten() {
         let path = entry.path();
-        if path
+        let is_alert_marker = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(alert::MARKER_PREFIX));
+        let ttl_secs = if is_alert_marker {
+            alert::MARKER_TTL_SECS
+        } else if path
             .extension()
             .and_then(|e| e.to_str())
-            != Some("lock")
+            == Some("lock")
         {
+            ttl_secs
+        } else {
             continue;
-        }
+        };
         let mtime = match fs::metadata(&path).and_then(|m| m.modified()) {
             Ok(m) => m,
             Err(_) => continue,
@@ -164,3 +334,318 @@ pub fn get_file_mtime(path: &str) -> Result<u64> {
         .as_secs();
     Ok(mtime)
 }
+
+/// Atomically replace `path` with the rendered line (temp file + rename),
+/// so bar programs watching the file never read a partial line. The temp
+/// file is named after the process so concurrent renders never share one.
+pub fn write_output_file(path: &Path, output: &str) -> Result<()> {
+    let mut temp_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid output file: {}", path.display()))?
+        .to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, format!("{}\n", output))?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_output_file_replaces_contents() {
+        let dir = std::env::temp_dir().join("ccusage-test-output-file");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("statusline.txt");
+
+        write_output_file(&path, "first").unwrap();
+        write_output_file(&path, "💰$1.23 │ 5h:40%").unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let leftover_temp = fs::read_dir(&dir)
+            .unwrap()
+            .count()
+            > 1;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents, "💰$1.23 │ 5h:40%\n");
+        assert!(!leftover_temp);
+    }
+
+    #[test]
+    fn test_cache_hit_skips_render() {
+        let dir = std::env::temp_dir().join("ccusage-test-cache-hit");
+        fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("transcript.jsonl");
+        fs::write(&transcript, "{}").unwrap();
+        let transcript = transcript
+            .to_string_lossy()
+            .to_string();
+        let cache_path = dir.join("session.lock");
+        let settings = CacheSettings::default();
+
+        let mut renders = 0;
+        let first = get_or_render(&cache_path, &transcript, &settings, |_| {
+            renders += 1;
+            Ok(RenderedStatusline {
+                output: "fresh output".to_string(),
+                history: RenderHistory::default(),
+            })
+        })
+        .unwrap();
+        let second = get_or_render(&cache_path, &transcript, &settings, |_| {
+            panic!("render must not run on a cache hit")
+        })
+        .unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(renders, 1);
+        assert_eq!(first, "fresh output");
+        assert_eq!(second, "fresh output");
+    }
+
+    #[test]
+    fn test_refresh_bypasses_cache() {
+        let dir = std::env::temp_dir().join("ccusage-test-cache-refresh");
+        fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("transcript.jsonl");
+        fs::write(&transcript, "{}").unwrap();
+        let transcript = transcript
+            .to_string_lossy()
+            .to_string();
+        let cache_path = dir.join("session.lock");
+        let settings = CacheSettings::default();
+        let render = |output: &'static str| {
+            move |_| {
+                Ok(RenderedStatusline {
+                    output: output.to_string(),
+                    history: RenderHistory::default(),
+                })
+            }
+        };
+
+        get_or_render_with(&cache_path, &transcript, &settings, false, render("cached")).unwrap();
+        let refreshed = get_or_render_with(
+            &cache_path,
+            &transcript,
+            &settings,
+            true,
+            render("refreshed"),
+        )
+        .unwrap();
+        // The fresh render is written back for later cached reads
+        let after =
+            get_or_render_with(&cache_path, &transcript, &settings, false, render("unused"))
+                .unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(refreshed, "refreshed");
+        assert_eq!(after, "refreshed");
+    }
+
+    #[test]
+    fn test_render_receives_previous_cost() {
+        let dir = std::env::temp_dir().join("ccusage-test-cost-delta");
+        fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("transcript.jsonl");
+        fs::write(&transcript, "{}").unwrap();
+        let transcript = transcript
+            .to_string_lossy()
+            .to_string();
+        let cache_path = dir.join("session.lock");
+        // Expire immediately so every call renders
+        let settings = CacheSettings {
+            output_cache_secs: 0,
+            min_render_interval_ms: 0,
+            ..CacheSettings::default()
+        };
+
+        let render = |cost: f64| {
+            move |previous: RenderHistory| {
+                Ok(RenderedStatusline {
+                    output: format!(
+                        "💰{}{}",
+                        crate::format::format_currency(cost, None),
+                        crate::format::format_cost_delta(previous.last_cost_usd, cost, None)
+                            .unwrap_or_default()
+                    ),
+                    history: RenderHistory {
+                        last_cost_usd: Some(cost),
+                        ..previous
+                    },
+                })
+            }
+        };
+        let first = get_or_render(&cache_path, &transcript, &settings, render(3.35)).unwrap();
+        let second = get_or_render(&cache_path, &transcript, &settings, render(3.42)).unwrap();
+        let third = get_or_render(&cache_path, &transcript, &settings, render(3.42)).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(first, "💰$3.35");
+        assert_eq!(second, "💰$3.42 (+$0.07)");
+        assert_eq!(third, "💰$3.42");
+    }
+
+    #[test]
+    fn test_cleanup_removes_old_alert_markers() {
+        let dir = std::env::temp_dir().join("ccusage-test-cleanup-markers");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let age = |name: &str, secs: u64| {
+            let path = dir.join(name);
+            File::create(&path)
+                .unwrap()
+                .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(secs))
+                .unwrap();
+            path
+        };
+        let old_lock = age("session.lock", 120);
+        let recent_marker = age("alert-1700000000-500", 120);
+        let old_marker = age("alert-1600000000-500", alert::MARKER_TTL_SECS + 60);
+        let other = age("pricing.json", alert::MARKER_TTL_SECS + 60);
+
+        cleanup_stale_locks(&dir, 60);
+        let exists = [&old_lock, &recent_marker, &old_marker, &other].map(|p| p.exists());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(exists, [false, true, false, true]);
+    }
+
+    #[test]
+    fn test_cache_dir_falls_back_when_unwritable() {
+        let fallback = std::env::temp_dir().join("ccusage-test-fallback");
+        let bases = vec![PathBuf::from("/proc/ccusage-nonexistent"), fallback.clone()];
+
+        let dir = first_writable_cache_dir(&bases, "claude").unwrap();
+        assert!(dir.starts_with(&fallback));
+
+        let transcript = dir.join("transcript.jsonl");
+        fs::write(&transcript, "{}").unwrap();
+        let transcript = transcript
+            .to_string_lossy()
+            .to_string();
+        let cache_path = dir.join("session.lock");
+        update_cache(
+            &cache_path,
+            &transcript,
+            "cached output",
+            RenderHistory::default(),
+        )
+        .unwrap();
+        let cached = try_get_cached(&cache_path, &transcript, 300, 0).unwrap();
+
+        fs::remove_dir_all(&fallback).unwrap();
+        assert_eq!(cached.as_deref(), Some("cached output"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_cache_dir_read_only_parent() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let parent = std::env::temp_dir().join("ccusage-test-readonly-parent");
+        fs::create_dir_all(&parent).unwrap();
+        fs::set_permissions(&parent, fs::Permissions::from_mode(0o555)).unwrap();
+        let dir = parent.join("ccusage-statusline-rs");
+
+        let result = create_cache_dir(&dir);
+        fs::set_permissions(&parent, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&parent).unwrap();
+
+        // Root ignores directory permissions, so there is nothing to report
+        if rustix::process::geteuid().is_root() {
+            return;
+        }
+        let message = format!("{:#}", result.unwrap_err());
+        assert!(
+            message.contains(
+                &dir.display()
+                    .to_string()
+            ),
+            "got {}",
+            message
+        );
+        assert!(message.contains("XDG_RUNTIME_DIR"), "got {}", message);
+        assert!(message.contains("os error 13"), "got {}", message);
+    }
+
+    #[test]
+    fn test_cache_dir_probes_only_new_dirs() {
+        let runtime_dir = std::env::temp_dir().join("ccusage-test-runtime-dir");
+        let _ = fs::remove_dir_all(&runtime_dir);
+        let bases = cache_dir_bases(
+            Some(
+                runtime_dir
+                    .clone()
+                    .into_os_string(),
+            ),
+            None,
+        );
+        assert_eq!(bases[0], runtime_dir);
+
+        let dir = first_writable_cache_dir(&bases, "claude").unwrap();
+        assert_eq!(dir, runtime_dir.join("ccusage-statusline-rs/claude"));
+        let probe = dir.join(".writable");
+        assert!(probe.exists());
+
+        // Later renders reuse the directory without writing to it
+        fs::remove_file(&probe).unwrap();
+        let again = first_writable_cache_dir(&bases, "claude").unwrap();
+        let probed = probe.exists();
+
+        fs::remove_dir_all(&runtime_dir).unwrap();
+        assert_eq!(again, dir);
+        assert!(!probed);
+    }
+
+    #[test]
+    fn test_cache_dir_error_lists_candidates() {
+        let bases = vec![PathBuf::from("/proc/ccusage-nonexistent")];
+        let message = first_writable_cache_dir(&bases, "claude")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("/proc/ccusage-nonexistent/ccusage-statusline-rs/claude: "),
+            "got {}",
+            message
+        );
+        assert!(message.contains(CACHE_DIR_HINT), "got {}", message);
+    }
+
+    #[test]
+    fn test_rapid_render_reuses_output_despite_transcript_change() {
+        let dir = std::env::temp_dir().join("ccusage-test-min-render");
+        fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("transcript.jsonl");
+        fs::write(&transcript, "{}").unwrap();
+        let transcript_str = transcript
+            .to_string_lossy()
+            .to_string();
+        let cache_path = dir.join("session.lock");
+
+        update_cache(
+            &cache_path,
+            &transcript_str,
+            "first render",
+            RenderHistory::default(),
+        )
+        .unwrap();
+
+        // Transcript written again right after the first render
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(&transcript)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        let within_interval = try_get_cached(&cache_path, &transcript_str, 300, 1000).unwrap();
+        let without_interval = try_get_cached(&cache_path, &transcript_str, 300, 0).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(within_interval.as_deref(), Some("first render"));
+        assert_eq!(without_interval, None);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::types::Block;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Output of `ccusage blocks --json`
+#[derive(Debug, Deserialize)]
+struct CcusageExport {
+    blocks: Vec<ExportBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportBlock {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    #[serde(default)]
+    is_active: bool,
+    #[serde(default)]
+    is_gap: bool,
+    #[serde(default)]
+    total_tokens: u64,
+    #[serde(default)]
+    token_counts: TokenCounts,
+    #[serde(rename = "costUSD")]
+    cost_usd: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenCounts {
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// Read the active block from a ccusage blocks export
+pub fn find_active_block(
+    export_path: &Path,
+    block_duration_hours: i64,
+    now: DateTime<Utc>,
+) -> Result<Block> {
+    let content = fs::read_to_string(export_path)
+        .with_context(|| format!("Failed to read ccusage export: {}", export_path.display()))?;
+    let export: CcusageExport =
+        serde_json::from_str(&content).context("Failed to parse ccusage export")?;
+
+    let active = export
+        .blocks
+        .iter()
+        .rev()
+        .find(|b| b.is_active && !b.is_gap && b.end_time > now);
+
+    Ok(match active {
+        Some(b) => Block {
+            start_time: b.start_time,
+            end_time: b.end_time,
+            cost_usd: b.cost_usd,
+            total_tokens: b.total_tokens,
+            cache_creation_tokens: b
+                .token_counts
+                .cache_creation_input_tokens,
+            cache_read_tokens: b
+                .token_counts
+                .cache_read_input_tokens,
+            is_active: true,
+            hours_remaining: Some(((b.end_time - now).num_seconds() as f64 / 3600.0).max(0.0)),
+            session_count: 0,
+            stale: false,
+            in_grace: false,
+            latest_source: None,
+            entry_costs: Vec::new(),
+        },
+        None => Block::inactive(now, block_duration_hours),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_export(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_active_block_from_export() {
+        let now = "2025-01-01T12:30:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let path = write_export(
+            "ccusage-test-export.json",
+            r#"{"blocks": [
+                {"id": "a", "startTime": "2025-01-01T02:00:00Z", "endTime": "2025-01-01T07:00:00Z",
+                 "isActive": false, "isGap": false, "totalTokens": 5000, "costUSD": 1.5},
+                {"id": "b", "startTime": "2025-01-01T10:00:00Z", "endTime": "2025-01-01T15:00:00Z",
+                 "isActive": true, "isGap": false, "totalTokens": 12000, "costUSD": 4.25}
+            ]}"#,
+        );
+
+        let block = find_active_block(&path, 5, now).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(block.is_active);
+        assert_eq!(block.cost_usd, 4.25);
+        assert_eq!(block.total_tokens, 12000);
+        assert_eq!(block.hours_remaining, Some(2.5));
+    }
+
+    #[test]
+    fn test_export_without_active_block() {
+        let now = Utc::now();
+        let path = write_export("ccusage-test-export-inactive.json", r#"{"blocks": []}"#);
+
+        let block = find_active_block(&path, 5, now).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!block.is_active);
+    }
+
+    #[test]
+    fn test_invalid_export_schema_is_error() {
+        let path = write_export(
+            "ccusage-test-export-invalid.json",
+            r#"{"blocks": [{"startTime": "nope"}]}"#,
+        );
+
+        let result = find_active_block(&path, 5, Utc::now());
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+}
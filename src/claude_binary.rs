@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::SystemTime;
 
@@ -14,9 +14,12 @@ struct VersionCache {
     binary_mtime: u64,
 }
 
-/// Get Claude binary path from PATH
-fn get_claude_binary_path() -> Option<PathBuf> {
-    which::which("claude").ok()
+/// `configured` as-is when set, else `claude` looked up on PATH
+fn resolve_binary_path(configured: Option<&Path>) -> Option<PathBuf> {
+    match configured {
+        Some(path) => Some(path.to_path_buf()),
+        None => which::which("claude").ok(),
+    }
 }
 
 /// Get binary modification time as unix timestamp
@@ -32,15 +35,14 @@ fn get_binary_mtime(path: &PathBuf) -> Option<u64> {
 }
 
 /// Get cached version if still valid (binary hasn't changed)
-fn get_cached_version() -> Option<String> {
+fn get_cached_version(binary_path: &PathBuf) -> Option<String> {
     let cache_dir = get_cache_dir().ok()?;
     let cache_path = cache_dir.join(VERSION_CACHE_FILE);
 
     let contents = fs::read_to_string(&cache_path).ok()?;
     let cache: VersionCache = serde_json::from_str(&contents).ok()?;
 
-    let binary_path = get_claude_binary_path()?;
-    let current_mtime = get_binary_mtime(&binary_path)?;
+    let current_mtime = get_binary_mtime(binary_path)?;
 
     if cache.binary_mtime == current_mtime {
         Some(cache.version)
@@ -64,9 +66,9 @@ fn save_version_cache(version: &str, mtime: u64) {
     }
 }
 
-/// Fetch version from `claude --version`
-fn fetch_claude_version() -> Option<String> {
-    let output = Command::new("claude")
+/// Fetch version from `<binary> --version`
+fn fetch_claude_version(binary: &Path) -> Option<String> {
+    let output = Command::new(binary)
         .arg("--version")
         .output()
         .ok()?;
@@ -85,29 +87,49 @@ fn fetch_claude_version() -> Option<String> {
         .map(String::from)
 }
 
-/// Get Claude Code version (cached based on binary mtime)
-pub fn get_version() -> Option<String> {
+/// Get Claude Code version (cached based on binary mtime) of the
+/// `configured` binary (`claude_binary_path`), else of `claude` on PATH
+pub fn get_version(configured: Option<&Path>) -> Option<String> {
+    let binary_path = resolve_binary_path(configured)?;
+
     // Try cache first
-    if let Some(version) = get_cached_version() {
+    if let Some(version) = get_cached_version(&binary_path) {
         return Some(version);
     }
 
     // Fetch fresh version
-    let version = fetch_claude_version()?;
+    let version = fetch_claude_version(&binary_path)?;
 
     // Cache it with binary mtime
-    if let Some(binary_path) = get_claude_binary_path()
-        && let Some(mtime) = get_binary_mtime(&binary_path)
-    {
+    if let Some(mtime) = get_binary_mtime(&binary_path) {
         save_version_cache(&version, mtime);
     }
 
     Some(version)
 }
 
-/// Get User-Agent string for API requests
-pub fn get_user_agent() -> String {
-    match get_version() {
+/// Get User-Agent string for API requests from the version of the
+/// `configured` binary (`CCUSAGE_USER_AGENT` overrides it)
+pub fn get_user_agent(configured: Option<&Path>) -> String {
+    user_agent_from(
+        std::env::var("CCUSAGE_USER_AGENT")
+            .ok()
+            .as_deref(),
+        || get_version(configured),
+    )
+}
+
+/// `env_override` when set and non-empty, otherwise the User-Agent for the
+/// installed `version` (only looked up when needed)
+fn user_agent_from(env_override: Option<&str>, version: impl FnOnce() -> Option<String>) -> String {
+    match env_override {
+        Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+        _ => user_agent_for_version(version()),
+    }
+}
+
+fn user_agent_for_version(version: Option<String>) -> String {
+    match version {
         Some(version) => format!("claude-code/{}", version),
         None => "claude-code/unknown".to_string(),
     }
@@ -129,6 +151,25 @@ mod tests {
         assert_eq!(parsed.binary_mtime, 1234567890);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_configured_binary_path_is_used() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("ccusage-test-claude-binary");
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("claude-elsewhere");
+        fs::write(&script, "#!/bin/sh\necho '9.9.9 (Claude Code)'\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let binary = resolve_binary_path(Some(&script)).unwrap();
+        let version = fetch_claude_version(&binary);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(binary, script);
+        assert_eq!(version.as_deref(), Some("9.9.9"));
+    }
+
     #[test]
     fn test_user_agent_format() {
         // Test with known version
@@ -136,4 +177,24 @@ mod tests {
         assert!(ua.starts_with("claude-code/"));
         assert!(ua.contains('.'));
     }
+
+    #[test]
+    fn test_user_agent_for_version() {
+        assert_eq!(
+            user_agent_for_version(Some("2.0.71".to_string())),
+            "claude-code/2.0.71"
+        );
+        assert_eq!(user_agent_for_version(None), "claude-code/unknown");
+    }
+
+    #[test]
+    fn test_user_agent_env_override() {
+        let version = || Some("2.0.71".to_string());
+        assert_eq!(
+            user_agent_from(Some("corp-proxy-agent/1.0"), || unreachable!()),
+            "corp-proxy-agent/1.0"
+        );
+        assert_eq!(user_agent_from(Some(""), version), "claude-code/2.0.71");
+        assert_eq!(user_agent_from(None, version), "claude-code/2.0.71");
+    }
 }
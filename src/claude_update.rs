@@ -1,9 +1,10 @@
-use crate::cache::get_cache_dir;
+use crate::cache::{force_refresh, get_cache_dir};
 use crate::claude_binary;
 use crate::config::{StatusElement, StatuslineConfig};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -11,7 +12,6 @@ use std::time::Duration;
 const NPM_REGISTRY_URL: &str = "https://registry.npmjs.org/@anthropic-ai/claude-code";
 const GCS_STABLE_URL: &str = "https://storage.googleapis.com/claude-code-dist-86c565f3-f756-42ad-8dfa-d59b1c096819/claude-code-releases/stable";
 const UPDATE_CHECK_CACHE_TTL: Duration = Duration::from_secs(1800); // 30 minutes
-const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -21,21 +21,34 @@ pub enum VersionChannel {
     Latest,
 }
 
+pub const DEFAULT_NPM_DIST_TAG: &str = "latest";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct NpmRegistryResponse {
     #[serde(rename = "dist-tags")]
-    dist_tags: DistTags,
+    dist_tags: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct DistTags {
-    latest: String,
+impl NpmRegistryResponse {
+    /// Version published under `tag`, falling back to `latest`
+    fn version_for_tag(&self, tag: &str) -> Option<&str> {
+        self.dist_tags
+            .get(tag)
+            .or_else(|| {
+                self.dist_tags
+                    .get(DEFAULT_NPM_DIST_TAG)
+            })
+            .map(String::as_str)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct UpdateCache {
     latest_version: Option<String>,
     checked_at: DateTime<Utc>,
+    /// npm dist-tag the version was read from (Latest channel only)
+    #[serde(default)]
+    dist_tag: Option<String>,
 }
 
 fn get_cache_path(channel: VersionChannel) -> Result<PathBuf> {
@@ -62,7 +75,14 @@ fn write_cache(channel: VersionChannel, cache: &UpdateCache) -> Result<()> {
     Ok(())
 }
 
-fn is_cache_fresh(cache: &UpdateCache) -> bool {
+fn is_cache_fresh(cache: &UpdateCache, dist_tag: Option<&str>) -> bool {
+    if cache
+        .dist_tag
+        .as_deref()
+        != dist_tag
+    {
+        return false;
+    }
     let elapsed = Utc::now() - cache.checked_at;
     elapsed
         .to_std()
@@ -70,10 +90,12 @@ fn is_cache_fresh(cache: &UpdateCache) -> bool {
         .unwrap_or(false)
 }
 
-fn fetch_latest_version(channel: VersionChannel) -> Result<String> {
-    let client = reqwest::blocking::Client::builder()
-        .timeout(REQUEST_TIMEOUT)
-        .build()?;
+fn fetch_latest_version(
+    channel: VersionChannel,
+    dist_tag: &str,
+    timeout: Duration,
+) -> Result<String> {
+    let client = crate::net::client(timeout)?;
 
     match channel {
         VersionChannel::Stable => {
@@ -113,9 +135,9 @@ fn fetch_latest_version(channel: VersionChannel) -> Result<String> {
                 .json()
                 .context("Failed to parse npm registry response")?;
 
-            Ok(data
-                .dist_tags
-                .latest)
+            data.version_for_tag(dist_tag)
+                .map(str::to_string)
+                .with_context(|| format!("npm registry has no '{}' or latest dist-tag", dist_tag))
         }
     }
 }
@@ -134,9 +156,7 @@ fn compare_versions(current: &str, latest: &str) -> bool {
 }
 
 /// Determine which version channel to use based on enabled elements
-fn get_version_channel() -> Option<VersionChannel> {
-    let config = StatuslineConfig::load().ok()?;
-
+fn get_version_channel(config: &StatuslineConfig) -> Option<VersionChannel> {
     // Check which update element is enabled (prefer stable if both somehow enabled)
     if config
         .enabled_elements
@@ -154,26 +174,43 @@ fn get_version_channel() -> Option<VersionChannel> {
 }
 
 /// Check if a Claude Code update is available.
-/// Returns Some(version) if an update is available, None otherwise.
+/// Returns Some((current, latest)) if an update is available, None otherwise.
 /// Caches results for 30 minutes per channel.
-pub fn check_update_available() -> Option<String> {
-    let channel = get_version_channel()?;
-    let current = claude_binary::get_version()?;
+pub fn check_update_available(config: &StatuslineConfig) -> Option<(String, String)> {
+    let channel = get_version_channel(config)?;
+    let current = claude_binary::get_version(
+        config
+            .claude_binary_path
+            .as_deref(),
+    )?;
+    let dist_tag = match channel {
+        VersionChannel::Stable => None,
+        VersionChannel::Latest => Some(
+            config
+                .npm_dist_tag
+                .as_str(),
+        ),
+    };
 
     // Try to read cache first
-    if let Some(cache) = read_cache(channel)
-        && is_cache_fresh(&cache)
+    if !force_refresh()
+        && let Some(cache) = read_cache(channel)
+        && is_cache_fresh(&cache, dist_tag)
     {
         if let Some(ref latest) = cache.latest_version
             && compare_versions(&current, latest)
         {
-            return Some(latest.clone());
+            return Some((current, latest.clone()));
         }
         return None;
     }
 
     // Cache miss or stale - fetch new data
-    let latest_version = match fetch_latest_version(channel) {
+    let latest_version = match fetch_latest_version(
+        channel,
+        dist_tag.unwrap_or(DEFAULT_NPM_DIST_TAG),
+        config.network_timeout(),
+    ) {
         Ok(version) => Some(version),
         Err(_) => {
             // Fail silently, use old cache if available
@@ -185,14 +222,15 @@ pub fn check_update_available() -> Option<String> {
     let new_cache = UpdateCache {
         latest_version: latest_version.clone(),
         checked_at: Utc::now(),
+        dist_tag: dist_tag.map(str::to_string),
     };
     let _ = write_cache(channel, &new_cache);
 
     // Check if update available
-    if let Some(ref latest) = latest_version
-        && compare_versions(&current, latest)
+    if let Some(latest) = latest_version
+        && compare_versions(&current, &latest)
     {
-        return Some(latest.clone());
+        return Some((current, latest));
     }
 
     None
@@ -210,4 +248,35 @@ mod tests {
         assert!(!compare_versions("1.0.1", "1.0.0"));
         assert!(!compare_versions("1.0.0", "1.0.0"));
     }
+
+    #[test]
+    fn test_npm_dist_tags() {
+        let response: NpmRegistryResponse = serde_json::from_str(
+            r#"{"name": "@anthropic-ai/claude-code",
+                "dist-tags": {"latest": "2.1.0", "next": "2.2.0-beta.1", "stable": "2.0.71"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(response.version_for_tag("latest"), Some("2.1.0"));
+        assert_eq!(response.version_for_tag("next"), Some("2.2.0-beta.1"));
+        assert_eq!(response.version_for_tag("stable"), Some("2.0.71"));
+        // Unknown tags fall back to latest
+        assert_eq!(response.version_for_tag("canary"), Some("2.1.0"));
+
+        let no_latest: NpmRegistryResponse =
+            serde_json::from_str(r#"{"dist-tags": {"next": "2.2.0"}}"#).unwrap();
+        assert_eq!(no_latest.version_for_tag("canary"), None);
+    }
+
+    #[test]
+    fn test_cache_tied_to_dist_tag() {
+        let cache = UpdateCache {
+            latest_version: Some("2.2.0".to_string()),
+            checked_at: Utc::now(),
+            dist_tag: Some("next".to_string()),
+        };
+        assert!(is_cache_fresh(&cache, Some("next")));
+        assert!(!is_cache_fresh(&cache, Some("latest")));
+        assert!(!is_cache_fresh(&cache, None));
+    }
 }
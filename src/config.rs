@@ -1,27 +1,40 @@
+use crate::api_usage::FetchOptions;
+use crate::blocks::ScanOptions;
+use crate::format::{ApiMetricStyle, BurnRateStyle, ClockStyle, NumberFormat};
 use crate::paths::claude_config_dir;
 use anyhow::Result;
 use inquire::ui::{RenderConfig, Styled};
 use inquire::{CustomType, MultiSelect, Select};
+use owo_colors::AnsiColors;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum StatusElement {
     Model,
     BlockCost,
+    EffectiveRate,
     TimeRemaining5h,
     TimeRemaining7d,
+    BlockWindow,
     BurnRate,
     BurnRateEta,
     Context,
+    Sessions,
+    SessionCost,
     ApiMetrics5h,
     ApiMetrics7d,
     ApiMetricsSonnet,
     UpdateStable,
     UpdateLatest,
+    TokenExpiry,
+    LimitEta,
     Directory,
 }
 
@@ -30,26 +43,50 @@ const API_DEPENDENT_ELEMENTS: &[StatusElement] = &[
     StatusElement::TimeRemaining7d,
     StatusElement::BurnRate,
     StatusElement::BurnRateEta,
+    StatusElement::LimitEta,
+    StatusElement::ApiMetrics5h,
+    StatusElement::ApiMetrics7d,
+    StatusElement::ApiMetricsSonnet,
+];
+
+/// Elements still shown when `hide_when_idle` is set and no block is active
+const IDLE_VISIBLE_ELEMENTS: &[StatusElement] = &[StatusElement::Model, StatusElement::Directory];
+
+/// Elements colored by thresholds; static color overrides do not apply
+const THRESHOLD_COLORED_ELEMENTS: &[StatusElement] = &[
+    StatusElement::BurnRate,
+    StatusElement::BurnRateEta,
+    StatusElement::Context,
     StatusElement::ApiMetrics5h,
     StatusElement::ApiMetrics7d,
     StatusElement::ApiMetricsSonnet,
 ];
 
 impl StatusElement {
+    pub fn is_threshold_colored(&self) -> bool {
+        THRESHOLD_COLORED_ELEMENTS.contains(self)
+    }
+
     fn label(&self) -> &'static str {
         match self {
             Self::Model => "🤖 Model",
             Self::BlockCost => "💰 Block cost",
+            Self::EffectiveRate => "💲 Effective rate",
             Self::TimeRemaining5h => "🕑 Time remaining (5h)",
             Self::TimeRemaining7d => "📅 Time remaining (7d)",
+            Self::BlockWindow => "🕐 Block window",
             Self::BurnRate => "🔥 Burn rate",
             Self::BurnRateEta => "⏱ Coding time remaining",
             Self::Context => "🧠 Context",
+            Self::Sessions => "👥 Sessions",
+            Self::SessionCost => "🧾 Session cost",
             Self::ApiMetrics5h => "📊 API metrics (5h)",
             Self::ApiMetrics7d => "📊 API metrics (7d)",
             Self::ApiMetricsSonnet => "📊 API metrics (Sonnet 7d)",
             Self::UpdateStable => "🔼 Update (stable)",
             Self::UpdateLatest => "🔼 Update (latest)",
+            Self::TokenExpiry => "🔑 Token expiry",
+            Self::LimitEta => "🎯 5h limit ETA",
             Self::Directory => "📁 Directory",
         }
     }
@@ -58,8 +95,10 @@ impl StatusElement {
         match self {
             Self::Model => "Currently active model name.",
             Self::BlockCost => "Estimated cost of the current 5-hour billing block.",
+            Self::EffectiveRate => "Block cost per million tokens (see tokens_include_cache).",
             Self::TimeRemaining5h => "Time until 5-hour billing block resets.",
             Self::TimeRemaining7d => "Time until 7-day billing window resets.",
+            Self::BlockWindow => "Wall-clock start and end time of the current billing block.",
             Self::BurnRate => {
                 "Usage rate as % of limit. Color changes at warning/danger thresholds."
             }
@@ -67,6 +106,8 @@ impl StatusElement {
                 "Time remaining before hitting limit. Visible above burn rate show threshold."
             }
             Self::Context => "Current context window token usage and percentage.",
+            Self::Sessions => "Number of sessions contributing to the current billing block.",
+            Self::SessionCost => "Cost of the current session's transcript, across blocks.",
             Self::ApiMetrics5h => "5-hour API utilization percentage from Claude API.",
             Self::ApiMetrics7d => "7-day API utilization percentage from Claude API.",
             Self::ApiMetricsSonnet => "7-day Sonnet-specific utilization from Claude API.",
@@ -76,6 +117,10 @@ impl StatusElement {
             Self::UpdateLatest => {
                 "Notification when a new latest-channel Claude Code version is available."
             }
+            Self::TokenExpiry => "Warning when the OAuth access token is about to expire.",
+            Self::LimitEta => {
+                "Time until 5-hour utilization reaches 100%, from its recent rate of change."
+            }
             Self::Directory => "Current working directory path.",
         }
     }
@@ -84,16 +129,22 @@ impl StatusElement {
         vec![
             Self::Model,
             Self::BlockCost,
+            Self::EffectiveRate,
             Self::TimeRemaining5h,
             Self::TimeRemaining7d,
+            Self::BlockWindow,
             Self::BurnRate,
             Self::BurnRateEta,
             Self::Context,
+            Self::Sessions,
+            Self::SessionCost,
             Self::ApiMetrics5h,
             Self::ApiMetrics7d,
             Self::ApiMetricsSonnet,
             Self::UpdateStable,
             Self::UpdateLatest,
+            Self::TokenExpiry,
+            Self::LimitEta,
             Self::Directory,
         ]
     }
@@ -111,6 +162,31 @@ pub struct Thresholds {
     pub context_warning: u32,
     #[serde(default = "default_context_danger")]
     pub context_danger: u32,
+    /// API utilization % at which the 5h/7d percentages turn yellow
+    #[serde(default = "default_api_warn_pct")]
+    pub api_warn_pct: u32,
+    /// API utilization % at which the 5h/7d percentages turn red
+    #[serde(default = "default_api_critical_pct")]
+    pub api_critical_pct: u32,
+    #[serde(default = "default_token_expiry_warning_hours")]
+    pub token_expiry_warning_hours: u32,
+    /// Highlight the 5h clock in red below this many minutes remaining
+    #[serde(default = "default_time_warn_minutes")]
+    pub time_warn_minutes: u32,
+    /// Prefix the highlighted 5h clock with ⏰
+    #[serde(default = "default_true")]
+    pub time_warn_marker: bool,
+    #[serde(default = "default_burn_emoji")]
+    pub burn_emoji_ok: String,
+    #[serde(default = "default_burn_emoji")]
+    pub burn_emoji_warn: String,
+    #[serde(default = "default_burn_emoji")]
+    pub burn_emoji_critical: String,
+    /// Appended after the cost in the API burn rate, e.g. `$1.50/h`. Not
+    /// used with `burn_rate_unit_mode: per_block`, which shows the block
+    /// length instead, e.g. `$7.50/5h`.
+    #[serde(default = "default_burn_rate_unit")]
+    pub burn_rate_unit: String,
 }
 
 fn default_burn_rate_show() -> u32 {
@@ -128,6 +204,24 @@ fn default_context_warning() -> u32 {
 fn default_context_danger() -> u32 {
     70
 }
+fn default_api_warn_pct() -> u32 {
+    70
+}
+fn default_api_critical_pct() -> u32 {
+    90
+}
+fn default_token_expiry_warning_hours() -> u32 {
+    24
+}
+fn default_time_warn_minutes() -> u32 {
+    15
+}
+fn default_burn_emoji() -> String {
+    "🔥".to_string()
+}
+fn default_burn_rate_unit() -> String {
+    "/h".to_string()
+}
 
 impl Default for Thresholds {
     fn default() -> Self {
@@ -137,6 +231,15 @@ impl Default for Thresholds {
             burn_rate_danger: default_burn_rate_danger(),
             context_warning: default_context_warning(),
             context_danger: default_context_danger(),
+            api_warn_pct: default_api_warn_pct(),
+            api_critical_pct: default_api_critical_pct(),
+            token_expiry_warning_hours: default_token_expiry_warning_hours(),
+            time_warn_minutes: default_time_warn_minutes(),
+            time_warn_marker: true,
+            burn_emoji_ok: default_burn_emoji(),
+            burn_emoji_warn: default_burn_emoji(),
+            burn_emoji_critical: default_burn_emoji(),
+            burn_rate_unit: default_burn_rate_unit(),
         }
     }
 }
@@ -153,6 +256,17 @@ impl Thresholds {
     pub fn burn_rate_danger_ratio(&self) -> f64 {
         self.burn_rate_danger as f64 / 100.0
     }
+
+    /// Burn rate glyph for the band `ratio` falls in
+    pub fn burn_emoji(&self, ratio: f64) -> &str {
+        if ratio >= self.burn_rate_danger_ratio() {
+            &self.burn_emoji_critical
+        } else if ratio >= self.burn_rate_warning_ratio() {
+            &self.burn_emoji_warn
+        } else {
+            &self.burn_emoji_ok
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -163,6 +277,11 @@ pub struct CacheSettings {
     pub api_refresh_secs: u64,
     #[serde(default = "default_api_max_backoff_secs")]
     pub api_max_backoff_secs: u64,
+    #[serde(default = "default_min_render_interval_ms")]
+    pub min_render_interval_ms: u64,
+    /// Keep a compact Claude-only copy of the pricing map for faster startup
+    #[serde(default)]
+    pub pruned_pricing: bool,
 }
 
 fn default_output_cache_secs() -> u64 {
@@ -174,6 +293,9 @@ fn default_api_refresh_secs() -> u64 {
 fn default_api_max_backoff_secs() -> u64 {
     1800
 }
+fn default_min_render_interval_ms() -> u64 {
+    1000
+}
 
 impl Default for CacheSettings {
     fn default() -> Self {
@@ -181,15 +303,169 @@ impl Default for CacheSettings {
             output_cache_secs: default_output_cache_secs(),
             api_refresh_secs: default_api_refresh_secs(),
             api_max_backoff_secs: default_api_max_backoff_secs(),
+            min_render_interval_ms: default_min_render_interval_ms(),
+            pruned_pricing: false,
         }
     }
 }
 
+/// Where block usage data is read from
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageSource {
+    /// Scan Claude Code JSONL transcripts
+    #[default]
+    Jsonl,
+    /// Read blocks from a `ccusage blocks --json` export file
+    CcusageExport(PathBuf),
+}
+
+/// Which block wins when more than one is active at once (overlapping data
+/// from several project directories)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// The block that started last
+    #[default]
+    MostRecentStart,
+    /// The most expensive block
+    HighestCost,
+    /// One block spanning all of them, with their costs and tokens summed
+    Merge,
+}
+
+/// Which 7-day limits the API metrics segment shows
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SevenDayMode {
+    /// Pick the limit for the current model (Sonnet limit for Sonnet,
+    /// generic limit otherwise)
+    Auto,
+    /// Show each enabled 7-day element
+    #[default]
+    All,
+    /// Only the all-models 7-day limit
+    Generic,
+    /// Only the Sonnet 7-day limit
+    Sonnet,
+}
+
+impl SevenDayMode {
+    /// Resolve `Auto` against the current model; other modes are unchanged
+    pub fn resolve(self, model: &str) -> Self {
+        match self {
+            Self::Auto
+                if model
+                    .to_ascii_lowercase()
+                    .contains("sonnet") =>
+            {
+                Self::Sonnet
+            }
+            Self::Auto => Self::Generic,
+            other => other,
+        }
+    }
+}
+
+/// One limit inside the 📊 API metrics segment
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiMetric {
+    FiveHour,
+    SevenDay,
+    SevenDaySonnet,
+    SevenDayOpus,
+}
+
+/// How the API metrics show usage from several accounts
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountAggregation {
+    /// The highest utilization across accounts, e.g. `5h:40%`
+    #[default]
+    Max,
+    /// Every account's utilization, e.g. `5h:A40%/B10%`
+    List,
+}
+
+/// Period the API burn rate cost is given for
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BurnRateUnitMode {
+    /// Cost per hour, e.g. `$1.50/h`
+    #[default]
+    PerHour,
+    /// Cost projected over a whole block, e.g. `$7.50/5h`
+    PerBlock,
+}
+
+/// What the Sonnet 7d metric shows when the API reports no Sonnet limit
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SonnetWhenAbsent {
+    /// Leave the metric out
+    #[default]
+    Hide,
+    /// Show it as `S7d:0%`
+    ShowZero,
+    /// Show it as `S7d:-`
+    ShowDash,
+}
+
+/// How the block cost segment is presented
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanMode {
+    /// Show the block cost in dollars
+    #[default]
+    PayAsYouGo,
+    /// Flat-rate plans: show 5h utilization instead of dollars when the API
+    /// reports it
+    Flat,
+}
+
+/// What the spark glyph after the 5h percentage visualizes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SparkMode {
+    /// The fractional part of the percentage: 50.7% fills 7/10
+    #[default]
+    Fraction,
+    /// Progress toward the next 10% step: 57% fills 7/10
+    Decile,
+}
+
+/// How the 7-day reset time is shown
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetDisplay {
+    /// Time left, e.g. `📅2d3h`
+    #[default]
+    Relative,
+    /// Reset date in the display timezone, e.g. `📅Nov 5`
+    Absolute,
+    /// Both, e.g. `📅2d3h (Nov 5)`
+    Both,
+}
+
+/// How the model element names the current model
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelDisplay {
+    /// Claude Code's display name, e.g. `Opus 4.6 (1M)`
+    #[default]
+    Full,
+    /// Compact label for known models, e.g. `O4.6`
+    Short,
+    /// The raw model id, e.g. `claude-opus-4-6`
+    Id,
+}
+
 fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatuslineConfig {
     pub enabled_elements: Vec<StatusElement>,
     #[serde(default)]
@@ -198,6 +474,168 @@ pub struct StatuslineConfig {
     pub cache: CacheSettings,
     #[serde(default = "default_true")]
     pub show_emojis: bool,
+    #[serde(default = "default_block_duration_hours")]
+    pub block_duration_hours: i64,
+    #[serde(default)]
+    pub usage_source: UsageSource,
+    /// Flag the block as stale when no entry is newer than this (off when unset)
+    #[serde(default)]
+    pub stale_data_warn_minutes: Option<i64>,
+    /// Keep showing a block this long after it ends (off when 0)
+    #[serde(default)]
+    pub block_expiry_grace_minutes: i64,
+    /// Tie-break when several blocks are active at once
+    #[serde(default)]
+    pub active_block_overlap: OverlapPolicy,
+    /// Read only the newest N transcripts when looking for the active block (all when unset)
+    #[serde(default)]
+    pub max_scan_files: Option<usize>,
+    /// Parse transcripts on this many threads during block scans (serial when unset)
+    #[serde(default)]
+    pub scan_threads: Option<usize>,
+    /// Compute the burn rate over only this many trailing minutes (whole block when unset)
+    #[serde(default)]
+    pub burn_rate_window_minutes: Option<u64>,
+    #[serde(default)]
+    pub burn_rate_unit_mode: BurnRateUnitMode,
+    /// Named color overrides per element (e.g. `"directory": "cyan"`)
+    #[serde(default)]
+    pub colors: HashMap<StatusElement, String>,
+    /// Timezone for wall-clock times: `local` (default), `utc`, or an offset like `+02:00`
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default = "default_true")]
+    pub clock_24h: bool,
+    /// Append the 5h reset wall-clock time to the API segment, e.g. `5h:40% (→15:00)`
+    #[serde(default)]
+    pub show_5h_reset_time: bool,
+    /// Locale for number separators (e.g. `de`, `fr`); unset keeps `$1234.56`
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Show only model and directory while no block is active
+    #[serde(default)]
+    pub hide_when_idle: bool,
+    /// Drop block cost, burn rate and API usage segments that would show zero
+    #[serde(default)]
+    pub hide_zero_segments: bool,
+    /// Cut the rendered line to this many visible columns, ending it with `…`
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Prefix of the update notice
+    #[serde(default = "default_update_prefix")]
+    pub update_prefix: String,
+    /// Show the update notice as `current→latest` instead of only `latest`
+    #[serde(default)]
+    pub show_update_from: bool,
+    /// npm dist-tag followed by the latest-channel update check (e.g. `next`)
+    #[serde(default = "default_npm_dist_tag")]
+    pub npm_dist_tag: String,
+    /// Append a debug segment naming the project of the block's newest entry
+    #[serde(default)]
+    pub show_data_source: bool,
+    /// Append `💲!` when prices come from an expired cache because the
+    /// refresh failed
+    #[serde(default)]
+    pub show_pricing_stale: bool,
+    /// Render context tokens as `95.5k` / `1.2M` instead of whole thousands
+    #[serde(default)]
+    pub compact_tokens: bool,
+    /// Show the tokens left before auto-compaction in the context segment
+    #[serde(default)]
+    pub context_show_remaining: bool,
+    /// Lead the 5h time remaining with a clock emoji
+    #[serde(default = "default_true")]
+    pub time_show_clock: bool,
+    /// Count cache-read tokens toward the context size; without them only
+    /// input and cache-creation tokens are counted
+    #[serde(default = "default_true")]
+    pub context_counts_cache_read: bool,
+    #[serde(default)]
+    pub seven_day_mode: SevenDayMode,
+    /// Additional project directories scanned alongside the default ones
+    #[serde(default)]
+    pub extra_project_paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub reset_display: ResetDisplay,
+    #[serde(default)]
+    pub plan_mode: PlanMode,
+    #[serde(default)]
+    pub spark_mode: SparkMode,
+    /// Render the API limits as one piece, e.g. `📊5h40%·7d12%`
+    #[serde(default)]
+    pub compact_api_metrics: bool,
+    /// Limits shown in the API metrics segment, in order. Unset, they follow
+    /// the enabled `api_metrics_*` elements and `seven_day_mode`.
+    #[serde(default)]
+    pub api_metrics: Option<Vec<ApiMetric>>,
+    /// Credentials files of further accounts whose API usage is combined
+    /// with the logged-in account's
+    #[serde(default)]
+    pub extra_credentials: Vec<PathBuf>,
+    #[serde(default)]
+    pub account_aggregation: AccountAggregation,
+    #[serde(default)]
+    pub sonnet_metric_when_absent: SonnetWhenAbsent,
+    /// Separator between the limits in the API metrics segment
+    #[serde(default = "default_api_metrics_separator")]
+    pub api_metrics_separator: String,
+    #[serde(default)]
+    pub model_display: ModelDisplay,
+    /// Model text when Claude Code sends a blank display name
+    #[serde(default = "default_model_fallback_name")]
+    pub model_fallback_name: String,
+    /// Prefix the model with the workspace basename, e.g. `[my-crate] 🤖Sonnet`
+    #[serde(default)]
+    pub show_project_in_model: bool,
+    /// Append the block cost increase since the previous render
+    #[serde(default)]
+    pub show_cost_delta: bool,
+    /// Show the block cost next to the 5h utilization, e.g. `$3.42 / 40% used`
+    #[serde(default)]
+    pub show_cost_with_limit: bool,
+    /// Desktop notification each time the block cost passes a multiple of
+    /// this amount (off when unset)
+    #[serde(default)]
+    pub alert_cost_usd: Option<f64>,
+    /// Count cache read/write tokens in displayed token figures
+    #[serde(default = "default_true")]
+    pub tokens_include_cache: bool,
+    /// Decimals shown in API utilization percentages, e.g. 1 for `5h:40.3%`
+    #[serde(default)]
+    pub api_pct_decimals: u8,
+    /// Timeout for pricing, usage API and update-check requests
+    #[serde(default = "default_network_timeout_secs")]
+    pub network_timeout_secs: u64,
+    /// Claude Code binary to run for its version, when `claude` is not on
+    /// the statusline's PATH
+    #[serde(default)]
+    pub claude_binary_path: Option<PathBuf>,
+}
+
+const MAX_API_PCT_DECIMALS: u8 = 2;
+
+fn default_api_metrics_separator() -> String {
+    " ".to_string()
+}
+
+fn default_model_fallback_name() -> String {
+    "Claude".to_string()
+}
+
+fn default_network_timeout_secs() -> u64 {
+    crate::net::DEFAULT_TIMEOUT_SECS
+}
+
+fn default_block_duration_hours() -> i64 {
+    5
+}
+
+fn default_npm_dist_tag() -> String {
+    crate::claude_update::DEFAULT_NPM_DIST_TAG.to_string()
+}
+
+fn default_update_prefix() -> String {
+    "🔼".to_string()
 }
 
 impl Default for StatuslineConfig {
@@ -217,21 +655,250 @@ impl Default for StatuslineConfig {
             thresholds: Thresholds::default(),
             cache: CacheSettings::default(),
             show_emojis: true,
+            block_duration_hours: default_block_duration_hours(),
+            usage_source: UsageSource::default(),
+            stale_data_warn_minutes: None,
+            block_expiry_grace_minutes: 0,
+            active_block_overlap: OverlapPolicy::default(),
+            max_scan_files: None,
+            scan_threads: None,
+            burn_rate_window_minutes: None,
+            burn_rate_unit_mode: BurnRateUnitMode::default(),
+            colors: HashMap::new(),
+            timezone: None,
+            clock_24h: true,
+            show_5h_reset_time: false,
+            locale: None,
+            hide_when_idle: false,
+            hide_zero_segments: false,
+            max_length: None,
+            update_prefix: default_update_prefix(),
+            show_update_from: false,
+            npm_dist_tag: default_npm_dist_tag(),
+            show_data_source: false,
+            show_pricing_stale: false,
+            compact_tokens: false,
+            time_show_clock: true,
+            context_show_remaining: false,
+            context_counts_cache_read: true,
+            seven_day_mode: SevenDayMode::default(),
+            extra_project_paths: Vec::new(),
+            reset_display: ResetDisplay::default(),
+            plan_mode: PlanMode::default(),
+            spark_mode: SparkMode::default(),
+            compact_api_metrics: false,
+            api_metrics: None,
+            extra_credentials: Vec::new(),
+            account_aggregation: AccountAggregation::default(),
+            sonnet_metric_when_absent: SonnetWhenAbsent::default(),
+            api_metrics_separator: default_api_metrics_separator(),
+            model_display: ModelDisplay::default(),
+            model_fallback_name: default_model_fallback_name(),
+            show_project_in_model: false,
+            show_cost_delta: false,
+            show_cost_with_limit: false,
+            alert_cost_usd: None,
+            tokens_include_cache: true,
+            api_pct_decimals: 0,
+            network_timeout_secs: default_network_timeout_secs(),
+            claude_binary_path: None,
         }
     }
 }
 
 impl StatuslineConfig {
+    /// Enabled elements to render, given whether a block is active
+    pub fn visible_elements(&self, block_active: bool) -> Vec<StatusElement> {
+        let hide_idle = self.hide_when_idle && !block_active;
+        self.enabled_elements
+            .iter()
+            .filter(|e| !hide_idle || IDLE_VISIBLE_ELEMENTS.contains(e))
+            .cloned()
+            .collect()
+    }
+
     pub fn needs_api(&self) -> bool {
         self.enabled_elements
             .iter()
-            .any(|e| API_DEPENDENT_ELEMENTS.contains(e))
+            .any(|e| {
+                API_DEPENDENT_ELEMENTS.contains(e)
+                    || (*e == StatusElement::BlockCost && self.plan_mode == PlanMode::Flat)
+            })
     }
 
-    fn config_path() -> Result<PathBuf> {
+    /// Timeout of every outbound request
+    pub fn network_timeout(&self) -> Duration {
+        Duration::from_secs(self.network_timeout_secs)
+    }
+
+    /// Cache and request settings for `api_usage::fetch_usage`
+    pub fn fetch_options(&self) -> FetchOptions<'_> {
+        FetchOptions {
+            cache: &self.cache,
+            extra_credentials: &self.extra_credentials,
+            timeout: self.network_timeout(),
+            claude_binary: self
+                .claude_binary_path
+                .as_deref(),
+        }
+    }
+
+    /// Block scan settings for `find_active_block`
+    pub fn scan_options(&self) -> ScanOptions {
+        ScanOptions {
+            block_duration_hours: self.block_duration_hours,
+            stale_after_minutes: self.stale_data_warn_minutes,
+            grace_minutes: self.block_expiry_grace_minutes,
+            overlap: self.active_block_overlap,
+            max_files: self.max_scan_files,
+            threads: self
+                .scan_threads
+                .unwrap_or(1),
+        }
+    }
+
+    /// Clock style for the 5h reset time, when `show_5h_reset_time` is set
+    pub fn five_hour_reset_clock(&self) -> Option<ClockStyle<'_>> {
+        self.show_5h_reset_time
+            .then_some(ClockStyle {
+                timezone: self
+                    .timezone
+                    .as_deref(),
+                clock_24h: self.clock_24h,
+            })
+    }
+
+    /// Block length to project the API burn rate over, when
+    /// `burn_rate_unit_mode` is `per_block`
+    pub fn burn_rate_block_hours(&self) -> Option<i64> {
+        (self.burn_rate_unit_mode == BurnRateUnitMode::PerBlock)
+            .then_some(self.block_duration_hours)
+    }
+
+    /// Separators for `locale`, `en` for unknown ones; `None` keeps the plain
+    /// `$1234.56`
+    pub fn number_format(&self) -> Option<NumberFormat> {
+        let locale = self
+            .locale
+            .as_deref()?;
+        Some(NumberFormat::from_locale(locale).unwrap_or(NumberFormat::EN))
+    }
+
+    /// How the burn rate renders, showing the rate without its ETA
+    pub fn burn_rate_style(&self) -> BurnRateStyle<'_> {
+        BurnRateStyle {
+            show_rate: true,
+            show_eta: false,
+            hide_zero: self.hide_zero_segments,
+            per_block_hours: self.burn_rate_block_hours(),
+            number_format: self.number_format(),
+            thresholds: &self.thresholds,
+        }
+    }
+
+    /// How the API metrics segment renders each limit
+    pub fn api_metric_style(&self) -> ApiMetricStyle<'_> {
+        ApiMetricStyle {
+            spark_mode: self.spark_mode,
+            hide_zero: self.hide_zero_segments,
+            pct_decimals: self.api_pct_decimals,
+            reset_clock: self.five_hour_reset_clock(),
+            per_account: self.account_aggregation == AccountAggregation::List,
+            sonnet_absent: self.sonnet_metric_when_absent,
+            thresholds: &self.thresholds,
+        }
+    }
+
+    /// API limits to show, in order, for the enabled `elements` and the
+    /// current `model`. An explicit `api_metrics` list is used as-is.
+    pub fn api_metric_order(&self, elements: &[StatusElement], model: &str) -> Vec<ApiMetric> {
+        if let Some(metrics) = &self.api_metrics {
+            return metrics.clone();
+        }
+
+        let any_7d = elements.contains(&StatusElement::ApiMetrics7d)
+            || elements.contains(&StatusElement::ApiMetricsSonnet);
+        let (show_7d, show_sonnet) = match self
+            .seven_day_mode
+            .resolve(model)
+        {
+            SevenDayMode::Generic => (any_7d, false),
+            SevenDayMode::Sonnet => (false, any_7d),
+            SevenDayMode::All | SevenDayMode::Auto => (
+                elements.contains(&StatusElement::ApiMetrics7d),
+                elements.contains(&StatusElement::ApiMetricsSonnet),
+            ),
+        };
+
+        [
+            (
+                elements.contains(&StatusElement::ApiMetrics5h),
+                ApiMetric::FiveHour,
+            ),
+            (show_7d, ApiMetric::SevenDay),
+            (show_sonnet, ApiMetric::SevenDaySonnet),
+        ]
+        .into_iter()
+        .filter_map(|(shown, metric)| shown.then_some(metric))
+        .collect()
+    }
+
+    /// Configured color override for `element`. Unknown names keep the
+    /// default color; `validate` reports them when the config is loaded.
+    pub fn element_color(&self, element: &StatusElement) -> Option<AnsiColors> {
+        self.colors
+            .get(element)
+            .and_then(|name| crate::format::parse_color(name))
+    }
+
+    fn legacy_config_path() -> Result<PathBuf> {
         Ok(claude_config_dir()?.join("ccusage-statusline-config.json"))
     }
 
+    /// Config file to read: the XDG location when `XDG_CONFIG_HOME` is set,
+    /// unless only the legacy file exists
+    fn config_path() -> Result<PathBuf> {
+        Ok(resolve_config_path(
+            xdg_config_home().as_deref(),
+            &Self::legacy_config_path()?,
+        ))
+    }
+
+    /// Copy of this config with `CCUSAGE_ENABLE` / `CCUSAGE_DISABLE` applied
+    pub fn with_env_overrides(&self) -> Self {
+        let mut config = self.clone();
+        config.apply_element_overrides(
+            std::env::var("CCUSAGE_ENABLE")
+                .ok()
+                .as_deref(),
+            std::env::var("CCUSAGE_DISABLE")
+                .ok()
+                .as_deref(),
+        );
+        config
+    }
+
+    /// Append `enable` elements not already present, then remove `disable`
+    /// elements, so disabling wins when an element is named in both
+    fn apply_element_overrides(&mut self, enable: Option<&str>, disable: Option<&str>) {
+        if let Some(list) = enable {
+            for element in parse_element_list(list) {
+                if !self
+                    .enabled_elements
+                    .contains(&element)
+                {
+                    self.enabled_elements
+                        .push(element);
+                }
+            }
+        }
+        if let Some(list) = disable {
+            let disabled = parse_element_list(list);
+            self.enabled_elements
+                .retain(|e| !disabled.contains(e));
+        }
+    }
+
     pub fn load() -> Result<Self> {
         let path = Self::config_path()?;
 
@@ -240,12 +907,159 @@ impl StatuslineConfig {
         }
 
         let content = fs::read_to_string(&path)?;
-        let config: Self = serde_json::from_str(&content)?;
+        let mut config: Self = serde_json::from_str(&content)?;
+        let warnings = config.validate();
+        // Piped renders run on every prompt; only warn where someone reads it
+        if std::io::stderr().is_terminal() {
+            for warning in warnings {
+                eprintln!("{}", warning);
+            }
+        }
         Ok(config)
     }
 
-    pub fn save(&self) -> Result<()> {
-        let path = Self::config_path()?;
+    /// Check invariants serde cannot express, resetting offending values to
+    /// their defaults. Returns one message per correction.
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let t = &mut self.thresholds;
+
+        if t.burn_rate_warning >= t.burn_rate_danger {
+            warnings.push(format!(
+                "burn_rate_warning ({}) must be below burn_rate_danger ({}); using defaults.",
+                t.burn_rate_warning, t.burn_rate_danger
+            ));
+            t.burn_rate_warning = default_burn_rate_warning();
+            t.burn_rate_danger = default_burn_rate_danger();
+        }
+        if t.context_warning >= t.context_danger {
+            warnings.push(format!(
+                "context_warning ({}) must be below context_danger ({}); using defaults.",
+                t.context_warning, t.context_danger
+            ));
+            t.context_warning = default_context_warning();
+            t.context_danger = default_context_danger();
+        }
+        if t.api_warn_pct >= t.api_critical_pct {
+            warnings.push(format!(
+                "api_warn_pct ({}) must be below api_critical_pct ({}); using defaults.",
+                t.api_warn_pct, t.api_critical_pct
+            ));
+            t.api_warn_pct = default_api_warn_pct();
+            t.api_critical_pct = default_api_critical_pct();
+        }
+
+        let cache = &mut self.cache;
+        if cache.api_refresh_secs == 0 {
+            warnings.push(format!(
+                "api_refresh_secs must be positive; using {}.",
+                default_api_refresh_secs()
+            ));
+            cache.api_refresh_secs = default_api_refresh_secs();
+        }
+        if cache.api_max_backoff_secs < cache.api_refresh_secs {
+            warnings.push(format!(
+                "api_max_backoff_secs ({}) must be at least api_refresh_secs ({}); using {}.",
+                cache.api_max_backoff_secs, cache.api_refresh_secs, cache.api_refresh_secs
+            ));
+            cache.api_max_backoff_secs = cache.api_refresh_secs;
+        }
+
+        if self.api_pct_decimals > MAX_API_PCT_DECIMALS {
+            warnings.push(format!(
+                "api_pct_decimals must be at most {}; using {}.",
+                MAX_API_PCT_DECIMALS, MAX_API_PCT_DECIMALS
+            ));
+            self.api_pct_decimals = MAX_API_PCT_DECIMALS;
+        }
+        if let Some(locale) = &self.locale
+            && NumberFormat::from_locale(locale).is_none()
+        {
+            warnings.push(format!("Unknown locale '{}'; using en.", locale));
+        }
+        if self.network_timeout_secs == 0 {
+            warnings.push(format!(
+                "network_timeout_secs must be positive; using {}.",
+                default_network_timeout_secs()
+            ));
+            self.network_timeout_secs = default_network_timeout_secs();
+        }
+        if self.block_duration_hours <= 0 {
+            warnings.push(format!(
+                "block_duration_hours ({}) must be positive; using {}.",
+                self.block_duration_hours,
+                default_block_duration_hours()
+            ));
+            self.block_duration_hours = default_block_duration_hours();
+        }
+        if let Some(minutes) = self.stale_data_warn_minutes
+            && minutes <= 0
+        {
+            warnings.push(format!(
+                "stale_data_warn_minutes ({}) must be positive; disabling the stale warning.",
+                minutes
+            ));
+            self.stale_data_warn_minutes = None;
+        }
+        if self.block_expiry_grace_minutes < 0 {
+            warnings.push(format!(
+                "block_expiry_grace_minutes ({}) must not be negative; using 0.",
+                self.block_expiry_grace_minutes
+            ));
+            self.block_expiry_grace_minutes = 0;
+        }
+        if self
+            .npm_dist_tag
+            .trim()
+            .is_empty()
+        {
+            warnings.push("npm_dist_tag is empty; using latest.".to_string());
+            self.npm_dist_tag = default_npm_dist_tag();
+        }
+        if self.max_length == Some(0) {
+            warnings.push("max_length (0) must be positive; not truncating.".to_string());
+            self.max_length = None;
+        }
+        if self.scan_threads == Some(0) {
+            warnings.push("scan_threads (0) must be positive; scanning serially.".to_string());
+            self.scan_threads = None;
+        }
+        if self.burn_rate_window_minutes == Some(0) {
+            warnings.push(
+                "burn_rate_window_minutes (0) must be positive; using the whole block.".to_string(),
+            );
+            self.burn_rate_window_minutes = None;
+        }
+        if let Some(amount) = self.alert_cost_usd
+            && (amount <= 0.0 || amount.is_nan())
+        {
+            warnings.push(format!(
+                "alert_cost_usd ({}) must be positive; disabling cost alerts.",
+                amount
+            ));
+            self.alert_cost_usd = None;
+        }
+
+        self.colors
+            .retain(|element, name| {
+                let known = crate::format::parse_color(name).is_some();
+                if !known {
+                    warnings.push(format!(
+                        "Unknown color '{}' for {:?}; using the default color.",
+                        name, element
+                    ));
+                }
+                known
+            });
+
+        warnings
+    }
+
+    /// Write the config, moving a legacy file to the XDG location when
+    /// `XDG_CONFIG_HOME` is set. Returns the path written.
+    pub fn save(&self) -> Result<PathBuf> {
+        let legacy = Self::legacy_config_path()?;
+        let path = save_config_path(xdg_config_home().as_deref(), &legacy);
 
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
@@ -253,8 +1067,80 @@ impl StatuslineConfig {
 
         let content = serde_json::to_string_pretty(self)?;
         fs::write(&path, content)?;
-        Ok(())
+        if path != legacy && legacy.exists() {
+            fs::remove_file(&legacy)?;
+        }
+        Ok(path)
+    }
+}
+
+/// `XDG_CONFIG_HOME` when set to an absolute path, per the XDG spec
+fn xdg_config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
+
+fn xdg_config_path(xdg_config_home: &Path) -> PathBuf {
+    xdg_config_home
+        .join("ccusage-statusline")
+        .join("config.json")
+}
+
+/// The XDG config when `xdg_config_home` is given, falling back to `legacy`
+/// only while the legacy file exists and the XDG one does not
+fn resolve_config_path(xdg_config_home: Option<&Path>, legacy: &Path) -> PathBuf {
+    match xdg_config_home.map(xdg_config_path) {
+        Some(xdg) if xdg.exists() || !legacy.exists() => xdg,
+        _ => legacy.to_path_buf(),
+    }
+}
+
+/// Saves always target the XDG location when `xdg_config_home` is given
+fn save_config_path(xdg_config_home: Option<&Path>, legacy: &Path) -> PathBuf {
+    xdg_config_home.map_or_else(|| legacy.to_path_buf(), xdg_config_path)
+}
+
+/// Parse a comma-separated element list. Names match the config spelling
+/// (`burn_rate`) exactly; a name matching no element exactly covers every
+/// element it prefixes (`update` covers `update_stable` and `update_latest`,
+/// `api_metrics` the three API metrics). Unknown names are reported on stderr
+/// and skipped.
+fn parse_element_list(list: &str) -> Vec<StatusElement> {
+    let named: Vec<(StatusElement, String)> = StatusElement::all()
+        .into_iter()
+        .filter_map(|e| {
+            let name = serde_json::to_value(&e)
+                .ok()?
+                .as_str()?
+                .to_string();
+            Some((e, name))
+        })
+        .collect();
+
+    let mut elements = Vec::new();
+    for name in list
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+    {
+        let exact = named
+            .iter()
+            .find(|(_, s)| s == name);
+        let matched: Vec<StatusElement> = match exact {
+            Some((e, _)) => vec![e.clone()],
+            None => named
+                .iter()
+                .filter(|(_, s)| s.starts_with(name))
+                .map(|(e, _)| e.clone())
+                .collect(),
+        };
+        if matched.is_empty() {
+            eprintln!("Unknown statusline element '{}', ignoring", name);
+        }
+        elements.extend(matched);
     }
+    elements
 }
 
 enum MainMenu {
@@ -286,6 +1172,8 @@ enum ThresholdMenu {
     BurnRateDanger(u32),
     ContextWarning(u32),
     ContextDanger(u32),
+    TokenExpiryWarning(u32),
+    TimeWarning(u32),
     Back,
 }
 
@@ -310,6 +1198,15 @@ impl fmt::Display for ThresholdMenu {
             Self::ContextDanger(v) => {
                 write!(f, "Context danger          {v}%  (red color threshold)")
             }
+            Self::TokenExpiryWarning(v) => {
+                write!(
+                    f,
+                    "Token expiry warning    {v}h  (warn this long before expiry)"
+                )
+            }
+            Self::TimeWarning(v) => {
+                write!(f, "5h time warning         {v}m  (red clock below this)")
+            }
             Self::Back => write!(f, "Back"),
         }
     }
@@ -339,11 +1236,8 @@ pub fn run_config_menu() -> Result<()> {
             MainMenu::Thresholds => configure_thresholds(&mut config.thresholds)?,
             MainMenu::Help => print_help(),
             MainMenu::SaveAndExit => {
-                config.save()?;
-                println!(
-                    "\nConfiguration saved to {}",
-                    StatuslineConfig::config_path()?.display()
-                );
+                let path = config.save()?;
+                println!("\nConfiguration saved to {}", path.display());
                 println!(
                     "  Emojis: {}",
                     if config.show_emojis { "on" } else { "off" }
@@ -414,6 +1308,8 @@ fn configure_thresholds(thresholds: &mut Thresholds) -> Result<()> {
             ThresholdMenu::BurnRateDanger(thresholds.burn_rate_danger),
             ThresholdMenu::ContextWarning(thresholds.context_warning),
             ThresholdMenu::ContextDanger(thresholds.context_danger),
+            ThresholdMenu::TokenExpiryWarning(thresholds.token_expiry_warning_hours),
+            ThresholdMenu::TimeWarning(thresholds.time_warn_minutes),
             ThresholdMenu::Back,
         ];
 
@@ -457,6 +1353,22 @@ fn configure_thresholds(thresholds: &mut Thresholds) -> Result<()> {
                     thresholds.context_danger = v;
                 }
             }
+            ThresholdMenu::TokenExpiryWarning(_) => {
+                if let Some(v) = prompt_threshold(
+                    "Token expiry warning hours (0-200):",
+                    thresholds.token_expiry_warning_hours,
+                )? {
+                    thresholds.token_expiry_warning_hours = v;
+                }
+            }
+            ThresholdMenu::TimeWarning(_) => {
+                if let Some(v) = prompt_threshold(
+                    "5h time warning minutes (0-200):",
+                    thresholds.time_warn_minutes,
+                )? {
+                    thresholds.time_warn_minutes = v;
+                }
+            }
             ThresholdMenu::Back => break,
         }
     }
@@ -488,3 +1400,349 @@ fn print_help() {
     }
     println!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_path_resolution() {
+        let base = std::env::temp_dir().join("ccusage-test-config-path");
+        let _ = fs::remove_dir_all(&base);
+        let xdg_home = base.join("xdg");
+        let legacy = base.join(".claude/ccusage-statusline-config.json");
+        let xdg = xdg_home.join("ccusage-statusline/config.json");
+        fs::create_dir_all(
+            legacy
+                .parent()
+                .unwrap(),
+        )
+        .unwrap();
+        fs::create_dir_all(
+            xdg.parent()
+                .unwrap(),
+        )
+        .unwrap();
+
+        // XDG_CONFIG_HOME unset: always the legacy file
+        assert_eq!(resolve_config_path(None, &legacy), legacy);
+        assert_eq!(save_config_path(None, &legacy), legacy);
+
+        // Set, no file yet: the XDG location
+        let neither = resolve_config_path(Some(&xdg_home), &legacy);
+
+        // Set, only the legacy file: keep reading it, but save to XDG
+        fs::write(&legacy, "{}").unwrap();
+        let legacy_only = resolve_config_path(Some(&xdg_home), &legacy);
+        let save_target = save_config_path(Some(&xdg_home), &legacy);
+
+        // Set, both files: XDG wins
+        fs::write(&xdg, "{}").unwrap();
+        let both = resolve_config_path(Some(&xdg_home), &legacy);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(neither, xdg);
+        assert_eq!(legacy_only, legacy);
+        assert_eq!(save_target, xdg);
+        assert_eq!(both, xdg);
+    }
+
+    #[test]
+    fn test_hide_when_idle_keeps_model_and_directory() {
+        let mut config = StatuslineConfig {
+            hide_when_idle: true,
+            ..StatuslineConfig::default()
+        };
+        assert_eq!(
+            config.visible_elements(false),
+            vec![StatusElement::Model, StatusElement::Directory]
+        );
+        assert_eq!(config.visible_elements(true), config.enabled_elements);
+
+        config.enabled_elements = vec![StatusElement::BlockCost, StatusElement::BurnRate];
+        assert!(
+            config
+                .visible_elements(false)
+                .is_empty()
+        );
+
+        config.hide_when_idle = false;
+        assert_eq!(config.visible_elements(false), config.enabled_elements);
+    }
+
+    #[test]
+    fn test_validate_accepts_defaults() {
+        assert!(
+            StatuslineConfig::default()
+                .validate()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_validate_burn_rate_order() {
+        let mut config = StatuslineConfig::default();
+        config
+            .thresholds
+            .burn_rate_warning = 120;
+        let warnings = config.validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].contains("burn_rate_warning (120) must be below burn_rate_danger (100)")
+        );
+        assert_eq!(
+            config
+                .thresholds
+                .burn_rate_warning,
+            80
+        );
+        assert_eq!(
+            config
+                .thresholds
+                .burn_rate_danger,
+            100
+        );
+    }
+
+    #[test]
+    fn test_validate_context_order() {
+        let mut config = StatuslineConfig::default();
+        config
+            .thresholds
+            .context_danger = 50;
+        assert_eq!(
+            config
+                .validate()
+                .len(),
+            1
+        );
+        assert_eq!(
+            config
+                .thresholds
+                .context_warning,
+            50
+        );
+        assert_eq!(
+            config
+                .thresholds
+                .context_danger,
+            70
+        );
+    }
+
+    #[test]
+    fn test_validate_api_pct_order() {
+        let mut config = StatuslineConfig::default();
+        config
+            .thresholds
+            .api_warn_pct = 90;
+        assert_eq!(
+            config
+                .validate()
+                .len(),
+            1
+        );
+        assert_eq!(
+            config
+                .thresholds
+                .api_warn_pct,
+            70
+        );
+        assert_eq!(
+            config
+                .thresholds
+                .api_critical_pct,
+            90
+        );
+    }
+
+    #[test]
+    fn test_validate_api_intervals() {
+        let mut config = StatuslineConfig::default();
+        config
+            .cache
+            .api_refresh_secs = 0;
+        config
+            .cache
+            .api_max_backoff_secs = 60;
+        assert_eq!(
+            config
+                .validate()
+                .len(),
+            2
+        );
+        assert_eq!(
+            config
+                .cache
+                .api_refresh_secs,
+            300
+        );
+        assert_eq!(
+            config
+                .cache
+                .api_max_backoff_secs,
+            300
+        );
+    }
+
+    #[test]
+    fn test_validate_block_duration_and_stale_minutes() {
+        let mut config = StatuslineConfig {
+            block_duration_hours: 0,
+            stale_data_warn_minutes: Some(-5),
+            network_timeout_secs: 0,
+            block_expiry_grace_minutes: -3,
+            ..StatuslineConfig::default()
+        };
+        assert_eq!(
+            config
+                .validate()
+                .len(),
+            4
+        );
+        assert_eq!(config.network_timeout_secs, 5);
+        assert_eq!(config.block_duration_hours, 5);
+        assert_eq!(config.stale_data_warn_minutes, None);
+        assert_eq!(config.block_expiry_grace_minutes, 0);
+    }
+
+    #[test]
+    fn test_validate_unknown_colors() {
+        let mut config = StatuslineConfig::default();
+        config
+            .colors
+            .insert(StatusElement::Directory, "cyan".to_string());
+        config
+            .colors
+            .insert(StatusElement::Model, "chartreuse".to_string());
+        let warnings = config.validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("chartreuse"));
+        assert!(
+            config
+                .colors
+                .contains_key(&StatusElement::Directory)
+        );
+        assert!(
+            !config
+                .colors
+                .contains_key(&StatusElement::Model)
+        );
+    }
+
+    #[test]
+    fn test_seven_day_mode_resolves_by_model() {
+        let auto = SevenDayMode::Auto;
+        assert_eq!(
+            auto.resolve("claude-sonnet-4-5-20250929"),
+            SevenDayMode::Sonnet
+        );
+        assert_eq!(auto.resolve("claude-sonnet-4-6[1m]"), SevenDayMode::Sonnet);
+        assert_eq!(auto.resolve("Sonnet 4.5"), SevenDayMode::Sonnet);
+        assert_eq!(auto.resolve("claude-opus-4-6"), SevenDayMode::Generic);
+        assert_eq!(auto.resolve("claude-haiku-4-5"), SevenDayMode::Generic);
+        assert_eq!(
+            SevenDayMode::Generic.resolve("claude-sonnet-4-5"),
+            SevenDayMode::Generic
+        );
+        assert_eq!(
+            SevenDayMode::All.resolve("claude-opus-4-6"),
+            SevenDayMode::All
+        );
+    }
+
+    #[test]
+    fn test_api_metric_order() {
+        let mut config = StatuslineConfig::default();
+        let elements = config.visible_elements(true);
+        assert_eq!(
+            config.api_metric_order(&elements, "claude-opus-4-6"),
+            vec![ApiMetric::FiveHour, ApiMetric::SevenDay]
+        );
+
+        config.seven_day_mode = SevenDayMode::Auto;
+        assert_eq!(
+            config.api_metric_order(&elements, "claude-sonnet-4-5"),
+            vec![ApiMetric::FiveHour, ApiMetric::SevenDaySonnet]
+        );
+
+        // An explicit list picks and orders the limits on its own
+        let custom = StatuslineConfig {
+            api_metrics: serde_json::from_str(
+                r#"["seven_day_opus", "five_hour", "seven_day_sonnet"]"#,
+            )
+            .unwrap(),
+            ..StatuslineConfig::default()
+        };
+        assert_eq!(
+            custom.api_metric_order(&elements, "claude-sonnet-4-5"),
+            vec![
+                ApiMetric::SevenDayOpus,
+                ApiMetric::FiveHour,
+                ApiMetric::SevenDaySonnet
+            ]
+        );
+    }
+
+    #[test]
+    fn test_element_list_exact_before_prefix() {
+        assert_eq!(
+            parse_element_list("burn_rate"),
+            vec![StatusElement::BurnRate]
+        );
+        assert_eq!(
+            parse_element_list("update"),
+            vec![StatusElement::UpdateStable, StatusElement::UpdateLatest]
+        );
+        assert_eq!(
+            parse_element_list("api_metrics"),
+            vec![
+                StatusElement::ApiMetrics5h,
+                StatusElement::ApiMetrics7d,
+                StatusElement::ApiMetricsSonnet
+            ]
+        );
+        assert_eq!(
+            parse_element_list("time_remaining"),
+            vec![
+                StatusElement::TimeRemaining5h,
+                StatusElement::TimeRemaining7d
+            ]
+        );
+
+        let mut config = StatuslineConfig {
+            enabled_elements: vec![StatusElement::BurnRate, StatusElement::BurnRateEta],
+            ..StatuslineConfig::default()
+        };
+        config.apply_element_overrides(None, Some("burn_rate"));
+        assert_eq!(config.enabled_elements, vec![StatusElement::BurnRateEta]);
+    }
+
+    #[test]
+    fn test_element_overrides_add_and_remove() {
+        let mut config = StatuslineConfig {
+            enabled_elements: vec![
+                StatusElement::Model,
+                StatusElement::Context,
+                StatusElement::UpdateStable,
+            ],
+            ..StatuslineConfig::default()
+        };
+
+        config.apply_element_overrides(Some("sessions, model"), Some("context,update"));
+        assert_eq!(
+            config.enabled_elements,
+            vec![StatusElement::Model, StatusElement::Sessions]
+        );
+
+        config.apply_element_overrides(Some("burn_rate_eta,directory"), Some("directory,bogus"));
+        assert_eq!(
+            config.enabled_elements,
+            vec![
+                StatusElement::Model,
+                StatusElement::Sessions,
+                StatusElement::BurnRateEta
+            ]
+        );
+    }
+}
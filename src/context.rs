@@ -1,44 +1,107 @@
 use crate::{
+    blocks::read_transcript,
     paths::claude_config_dir,
     types::{ClaudeConfig, ContextInfo, ContextWindowData, HookData, UsageData},
 };
 use anyhow::Result;
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
+use chrono::DateTime;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
 
 const COMPACTED_CONTEXT_LIMIT: u64 = 155_000;
 const FULL_CONTEXT_LIMIT: u64 = 200_000;
 const EXTENDED_CONTEXT_LIMIT: u64 = 1_000_000;
 
 pub fn calculate_context(hook_data: &HookData) -> Result<Option<ContextInfo>> {
+    calculate_context_with(hook_data, None, true, true)
+}
+
+/// Like `calculate_context`, using already parsed transcript `entries`
+/// instead of reading `transcript_path`. Without `count_cache_read`, cache
+/// reads are left out of the context size. Without `with_remaining`, the
+/// tokens left are not looked up when Claude Code gave the context window.
+pub fn calculate_context_with(
+    hook_data: &HookData,
+    entries: Option<&[UsageData]>,
+    count_cache_read: bool,
+    with_remaining: bool,
+) -> Result<Option<ContextInfo>> {
+    let model_id = hook_data
+        .model
+        .id
+        .as_deref();
     if let Some(cw) = &hook_data.context_window
-        && let Some(info) = context_from_window(cw)
+        && let Some(info) = context_from_window(cw, model_id, count_cache_read, with_remaining)
     {
         return Ok(Some(info));
     }
 
-    calculate_context_from_transcript(
-        &hook_data.transcript_path,
-        hook_data
-            .model
-            .id
-            .as_deref(),
-    )
+    match entries {
+        Some(entries) => Ok(Some(context_from_entries(
+            entries,
+            model_id,
+            count_cache_read,
+        ))),
+        None => calculate_context_from_transcript(
+            &hook_data.transcript_path,
+            model_id,
+            count_cache_read,
+        ),
+    }
+}
+
+/// Tokens making up the context, with or without cache reads
+fn context_tokens(input: u64, cache_creation: u64, cache_read: u64, count_cache_read: bool) -> u64 {
+    if count_cache_read {
+        input + cache_creation + cache_read
+    } else {
+        input + cache_creation
+    }
 }
 
-fn context_from_window(cw: &ContextWindowData) -> Option<ContextInfo> {
-    let pct = cw.used_percentage?;
+/// Context from the hook's `context_window`. The context limit, which may
+/// read `.claude.json`, is only looked up `with_remaining`. `None` without
+/// `count_cache_read` when the window has no per-kind token counts, so the
+/// transcript is used instead.
+fn context_from_window(
+    cw: &ContextWindowData,
+    model_id: Option<&str>,
+    count_cache_read: bool,
+    with_remaining: bool,
+) -> Option<ContextInfo> {
+    let mut pct = cw.used_percentage?;
 
     let tokens = if let Some(usage) = &cw.current_usage {
-        usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens
-    } else {
+        let all = context_tokens(
+            usage.input_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens,
+            true,
+        );
+        let counted = context_tokens(
+            usage.input_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens,
+            count_cache_read,
+        );
+        // Claude Code's percentage includes cache reads; scale it down with
+        // the token count when they are left out
+        if all > 0 {
+            pct *= counted as f64 / all as f64;
+        }
+        counted
+    } else if count_cache_read {
         cw.total_input_tokens
             .unwrap_or(0)
+    } else {
+        return None;
     };
 
     Some(ContextInfo {
         tokens,
         percentage: (pct as u32).min(100),
+        remaining: with_remaining.then(|| get_context_limit(model_id).saturating_sub(tokens)),
     })
 }
 
@@ -57,73 +120,104 @@ fn get_context_limit(model_id: Option<&str>) -> u64 {
         return EXTENDED_CONTEXT_LIMIT;
     }
 
-    let config_dir = match claude_config_dir() {
-        Ok(d) => d,
-        Err(_) => return COMPACTED_CONTEXT_LIMIT,
-    };
-
-    let config_path = config_dir.join(".claude.json");
-
-    match fs::read_to_string(&config_path) {
-        Ok(content) => match serde_json::from_str::<ClaudeConfig>(&content) {
-            Ok(config) => {
-                if config.auto_compact_enabled {
-                    COMPACTED_CONTEXT_LIMIT
-                } else {
-                    FULL_CONTEXT_LIMIT
-                }
-            }
-            Err(_) => COMPACTED_CONTEXT_LIMIT,
-        },
-        Err(_) => COMPACTED_CONTEXT_LIMIT,
+    let auto_compact = claude_config_dir().map_or(true, |dir| auto_compact_enabled(&dir));
+    if auto_compact {
+        COMPACTED_CONTEXT_LIMIT
+    } else {
+        FULL_CONTEXT_LIMIT
     }
 }
 
+/// `autoCompactEnabled` from `.claude.json`, then `settings.json`; enabled
+/// when neither file sets it
+fn auto_compact_enabled(config_dir: &Path) -> bool {
+    [".claude.json", "settings.json"]
+        .iter()
+        .find_map(|name| read_auto_compact(&config_dir.join(name)))
+        .unwrap_or(true)
+}
+
+fn read_auto_compact(path: &Path) -> Option<bool> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<ClaudeConfig>(&content)
+        .ok()?
+        .auto_compact_enabled
+}
+
 fn calculate_context_from_transcript(
     transcript_path: &str,
     model_id: Option<&str>,
+    count_cache_read: bool,
 ) -> Result<Option<ContextInfo>> {
-    let file = match File::open(transcript_path) {
-        Ok(f) => f,
-        Err(_) => return Ok(None),
-    };
+    Ok(read_transcript(Path::new(transcript_path))
+        .map(|transcript| context_from_entries(&transcript.entries, model_id, count_cache_read)))
+}
 
-    let reader = BufReader::new(file);
-    let mut last_tokens: Option<u64> = None;
+/// Context for interactive mode from `CCUSAGE_TRANSCRIPT`, taking the model
+/// from the transcript itself. `None` when unset or unreadable.
+pub fn context_from_env_transcript(
+    transcript: Option<OsString>,
+    count_cache_read: bool,
+) -> Option<ContextInfo> {
+    context_from_transcript_file(Path::new(&transcript?), count_cache_read)
+}
 
-    for line in reader.lines() {
-        let line = line?;
-        if line
-            .trim()
-            .is_empty()
-        {
-            continue;
-        }
-        if let Ok(entry) = serde_json::from_str::<UsageData>(&line) {
-            let context = entry
+/// Context of the transcript at `path`, taking the model from the transcript
+/// itself. `None` when unreadable.
+pub fn context_from_transcript_file(path: &Path, count_cache_read: bool) -> Option<ContextInfo> {
+    let transcript = read_transcript(path)?;
+    let model_id = transcript
+        .entries
+        .iter()
+        .rev()
+        .find_map(|entry| {
+            entry
                 .message
-                .usage
-                .input_tokens
-                + entry
-                    .message
-                    .usage
-                    .cache_creation_input_tokens
-                + entry
-                    .message
-                    .usage
-                    .cache_read_input_tokens;
-            last_tokens = Some(context);
-        }
-    }
+                .model
+                .as_deref()
+        });
+    Some(context_from_entries(
+        &transcript.entries,
+        model_id,
+        count_cache_read,
+    ))
+}
 
-    let total_tokens = last_tokens.unwrap_or(0);
+/// Context size from the newest entry with usage. `entries` are deduplicated
+/// as returned by `read_usage_entries`; the newest is picked by parsed
+/// timestamp since their text order can disagree with it (fractional seconds,
+/// offsets). Ties and unparsable timestamps fall back to file order.
+fn context_from_entries(
+    entries: &[UsageData],
+    model_id: Option<&str>,
+    count_cache_read: bool,
+) -> ContextInfo {
+    let total_tokens = entries
+        .iter()
+        .filter_map(|entry| {
+            entry
+                .message
+                .usage
+                .as_ref()
+                .map(|usage| (DateTime::parse_from_rfc3339(&entry.timestamp).ok(), usage))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map_or(0, |(_, usage)| {
+            context_tokens(
+                usage.input_tokens,
+                usage.cache_creation_input_tokens,
+                usage.cache_read_input_tokens,
+                count_cache_read,
+            )
+        });
     let context_limit = get_context_limit(model_id);
     let percentage = ((total_tokens as f64 / context_limit as f64) * 100.0).min(100.0) as u32;
 
-    Ok(Some(ContextInfo {
+    ContextInfo {
         tokens: total_tokens,
         percentage,
-    }))
+        remaining: Some(context_limit.saturating_sub(total_tokens)),
+    }
 }
 
 #[cfg(test)]
@@ -142,11 +236,88 @@ mod tests {
                 cache_read_input_tokens: 2_000,
             }),
         };
-        let info = context_from_window(&cw).unwrap();
+        let info = context_from_window(&cw, None, true, true).unwrap();
         assert_eq!(info.tokens, 15_500);
         assert_eq!(info.percentage, 4);
     }
 
+    #[test]
+    fn test_context_remaining_tokens() {
+        let entry = |tokens: u64| -> Vec<UsageData> {
+            vec![
+                serde_json::from_str(&format!(
+                    r#"{{"timestamp":"2025-01-01T10:00:00Z","message":{{"usage":{{"input_tokens":{},"output_tokens":5}}}}}}"#,
+                    tokens
+                ))
+                .unwrap(),
+            ]
+        };
+        let model = Some("claude-opus-4-6");
+
+        assert_eq!(
+            context_from_entries(&entry(0), model, true).remaining,
+            Some(1_000_000)
+        );
+        assert_eq!(
+            context_from_entries(&entry(123_000), model, true).remaining,
+            Some(877_000)
+        );
+        assert_eq!(
+            context_from_entries(&entry(1_000_000), model, true).remaining,
+            Some(0)
+        );
+        // Over the limit shows nothing left rather than wrapping
+        let over = context_from_entries(&entry(1_200_000), model, true);
+        assert_eq!(over.remaining, Some(0));
+        assert_eq!(over.percentage, 100);
+
+        let cw = ContextWindowData {
+            used_percentage: Some(12.3),
+            total_input_tokens: Some(123_000),
+            current_usage: None,
+        };
+        let info = context_from_window(&cw, model, true, true).unwrap();
+        assert_eq!(info.remaining, Some(877_000));
+
+        // Not asked for: the limit is not looked up
+        let info = context_from_window(&cw, None, true, false).unwrap();
+        assert_eq!(info.remaining, None);
+        assert_eq!(info.tokens, 123_000);
+    }
+
+    #[test]
+    fn test_context_without_cache_read() {
+        let cw = ContextWindowData {
+            used_percentage: Some(40.0),
+            total_input_tokens: Some(80_000),
+            current_usage: Some(ContextUsage {
+                input_tokens: 10_000,
+                cache_creation_input_tokens: 10_000,
+                cache_read_input_tokens: 60_000,
+            }),
+        };
+        let with_read = context_from_window(&cw, None, true, true).unwrap();
+        assert_eq!(with_read.tokens, 80_000);
+        assert_eq!(with_read.percentage, 40);
+        let without_read = context_from_window(&cw, None, false, true).unwrap();
+        assert_eq!(without_read.tokens, 20_000);
+        assert_eq!(without_read.percentage, 10);
+
+        let entries: Vec<UsageData> = vec![
+            serde_json::from_str(
+                r#"{"timestamp":"2025-01-01T10:00:00Z","message":{"usage":{"input_tokens":10000,"output_tokens":5,"cache_creation_input_tokens":10000,"cache_read_input_tokens":60000}}}"#,
+            )
+            .unwrap(),
+        ];
+        let model = Some("claude-opus-4-6");
+        let with_read = context_from_entries(&entries, model, true);
+        assert_eq!(with_read.tokens, 80_000);
+        assert_eq!(with_read.percentage, 8);
+        let without_read = context_from_entries(&entries, model, false);
+        assert_eq!(without_read.tokens, 20_000);
+        assert_eq!(without_read.percentage, 2);
+    }
+
     #[test]
     fn test_context_from_window_200k() {
         let cw = ContextWindowData {
@@ -154,7 +325,7 @@ mod tests {
             total_input_tokens: Some(95_000),
             current_usage: None,
         };
-        let info = context_from_window(&cw).unwrap();
+        let info = context_from_window(&cw, None, true, true).unwrap();
         assert_eq!(info.tokens, 95_000);
         assert_eq!(info.percentage, 47);
     }
@@ -166,7 +337,7 @@ mod tests {
             total_input_tokens: Some(42_000),
             current_usage: None,
         };
-        assert!(context_from_window(&cw).is_none());
+        assert!(context_from_window(&cw, None, true, true).is_none());
     }
 
     #[test]
@@ -176,7 +347,7 @@ mod tests {
             total_input_tokens: None,
             current_usage: None,
         };
-        let info = context_from_window(&cw).unwrap();
+        let info = context_from_window(&cw, None, true, true).unwrap();
         assert_eq!(info.tokens, 0);
         assert_eq!(info.percentage, 10);
     }
@@ -218,6 +389,152 @@ mod tests {
         assert_eq!(info.tokens, 42_000);
     }
 
+    #[test]
+    fn test_calculate_context_without_cache_read_needs_usage() {
+        let hook = HookData {
+            session_id: "test".into(),
+            transcript_path: "/nonexistent".into(),
+            model: ModelInfo {
+                id: Some("claude-opus-4-6".into()),
+                display_name: "Opus 4.6 (1M context)".into(),
+            },
+            workspace: None,
+            context_window: Some(ContextWindowData {
+                used_percentage: Some(8.0),
+                total_input_tokens: Some(80_000),
+                current_usage: None,
+            }),
+        };
+        let entries: Vec<UsageData> = vec![
+            serde_json::from_str(
+                r#"{"timestamp":"2025-01-01T10:00:00Z","message":{"usage":{"input_tokens":10000,"output_tokens":5,"cache_creation_input_tokens":10000,"cache_read_input_tokens":60000}}}"#,
+            )
+            .unwrap(),
+        ];
+
+        // The window total includes cache reads: use the transcript instead
+        let info = calculate_context_with(&hook, Some(&entries), false, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.tokens, 20_000);
+        assert_eq!(info.percentage, 2);
+
+        let info = calculate_context_with(&hook, Some(&entries), true, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.tokens, 80_000);
+    }
+
+    #[test]
+    fn test_context_from_transcript_ignores_duplicates() {
+        let path = std::env::temp_dir().join("ccusage-test-context-dedup.jsonl");
+        let line = |id: &str, input: u64, cache_read: u64| {
+            format!(
+                r#"{{"timestamp":"2025-01-01T10:00:00Z","requestId":"req_{}","message":{{"id":"msg_{}","usage":{{"input_tokens":{},"output_tokens":5,"cache_read_input_tokens":{}}}}}}}"#,
+                id, id, input, cache_read
+            )
+        };
+        let transcript = [
+            line("1", 100, 10_000),
+            line("2", 200, 20_000),
+            // Retried write of the final entry with partial usage
+            line("2", 50, 0),
+        ]
+        .join("\n");
+        fs::write(&path, transcript).unwrap();
+
+        let info = calculate_context_from_transcript(
+            path.to_str()
+                .unwrap(),
+            None,
+            true,
+        )
+        .unwrap()
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(info.tokens, 20_200);
+    }
+
+    #[test]
+    fn test_context_from_shuffled_transcript() {
+        let line = |timestamp: &str, input: u64| {
+            format!(
+                r#"{{"timestamp":"{}","message":{{"usage":{{"input_tokens":{},"output_tokens":5}}}}}}"#,
+                timestamp, input
+            )
+        };
+        // Sorted as text, "10:00:00Z" lands after "10:00:00.500Z" and
+        // "11:30:00+02:00" (09:30 UTC) after both
+        let transcript = [
+            line("2025-01-01T10:00:00.500Z", 3000),
+            line("2025-01-01T09:00:00Z", 1000),
+            line("2025-01-01T11:30:00+02:00", 1500),
+            line("2025-01-01T10:00:00Z", 2000),
+        ]
+        .join("\n");
+        let entries = crate::blocks::read_usage_entries(std::io::Cursor::new(transcript)).unwrap();
+
+        let info = context_from_entries(&entries, None, true);
+        assert_eq!(info.tokens, 3000);
+    }
+
+    #[test]
+    fn test_context_from_env_transcript() {
+        let path = std::env::temp_dir().join("ccusage-test-context-env.jsonl");
+        fs::write(
+            &path,
+            r#"{"timestamp":"2025-01-01T10:00:00Z","message":{"id":"msg_1","model":"claude-opus-4-6","usage":{"input_tokens":100,"output_tokens":5,"cache_read_input_tokens":99900}}}"#,
+        )
+        .unwrap();
+
+        let info = context_from_env_transcript(
+            Some(
+                path.clone()
+                    .into_os_string(),
+            ),
+            true,
+        )
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // The model comes from the transcript, so the 1M limit applies
+        assert_eq!(info.tokens, 100_000);
+        assert_eq!(info.percentage, 10);
+
+        assert!(context_from_env_transcript(None, true).is_none());
+        assert!(context_from_env_transcript(Some(path.into_os_string()), true).is_none());
+    }
+
+    #[test]
+    fn test_auto_compact_sources() {
+        let dir = std::env::temp_dir().join("ccusage-test-auto-compact");
+        fs::create_dir_all(&dir).unwrap();
+        let claude_json = dir.join(".claude.json");
+        let settings_json = dir.join("settings.json");
+
+        // Neither file: compacted default
+        assert!(auto_compact_enabled(&dir));
+
+        // settings.json alone
+        fs::write(&settings_json, r#"{"autoCompactEnabled": false}"#).unwrap();
+        assert!(!auto_compact_enabled(&dir));
+
+        // .claude.json without the key still falls through to settings.json
+        fs::write(&claude_json, r#"{"numStartups": 3}"#).unwrap();
+        assert!(!auto_compact_enabled(&dir));
+
+        // .claude.json takes precedence when it sets the key
+        fs::write(&claude_json, r#"{"autoCompactEnabled": true}"#).unwrap();
+        assert!(auto_compact_enabled(&dir));
+
+        fs::write(&claude_json, r#"{"autoCompactEnabled": false}"#).unwrap();
+        fs::remove_file(&settings_json).unwrap();
+        assert!(!auto_compact_enabled(&dir));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_context_calculation_with_caching_compacted() {
         let tokens = 10 + 500 + 95000;
@@ -0,0 +1,93 @@
+use crate::types::Block;
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+const HEADER: &str = "start,end,cost_usd,total_tokens,is_active";
+
+/// Quote a field when it holds a comma, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Write `blocks` as CSV with a header row, one block per line
+pub fn write_blocks_csv(blocks: &[Block], mut out: impl Write) -> io::Result<()> {
+    writeln!(out, "{}", HEADER)?;
+    for block in blocks {
+        let fields = [
+            block
+                .start_time
+                .to_rfc3339(),
+            block
+                .end_time
+                .to_rfc3339(),
+            format!("{:.4}", block.cost_usd),
+            block
+                .total_tokens
+                .to_string(),
+            block
+                .is_active
+                .to_string(),
+        ];
+        let row: Vec<Cow<str>> = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_csv_field_escaping() {
+        assert_eq!(csv_field("12.5"), "12.5");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_write_blocks_csv() {
+        let start = "2025-01-01T10:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let mut block = Block::inactive(start, 5);
+        block.cost_usd = 1.23456;
+        block.total_tokens = 4200;
+        let mut active = Block::inactive(start + chrono::Duration::hours(6), 5);
+        active.is_active = true;
+
+        let mut out = Vec::new();
+        write_blocks_csv(&[block, active], &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let rows: Vec<&str> = csv
+            .lines()
+            .collect();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], HEADER);
+        assert_eq!(
+            rows[1],
+            "2025-01-01T10:00:00+00:00,2025-01-01T15:00:00+00:00,1.2346,4200,false"
+        );
+        assert_eq!(
+            rows[2],
+            "2025-01-01T16:00:00+00:00,2025-01-01T21:00:00+00:00,0.0000,0,true"
+        );
+        assert!(
+            rows.iter()
+                .all(|row| row
+                    .split(',')
+                    .count()
+                    == 5)
+        );
+    }
+}
@@ -1,15 +1,311 @@
-use crate::config::Thresholds;
-use crate::types::{ApiUsageData, Block, BurnRate, ContextInfo, LimitType, PlanType};
-use chrono::{Duration, Utc};
-use owo_colors::OwoColorize;
+use crate::config::{
+    ApiMetric, ModelDisplay, PlanMode, ResetDisplay, SonnetWhenAbsent, SparkMode, Thresholds,
+};
+use crate::types::{
+    ApiUsageData, Block, BurnRate, ContextInfo, LimitType, ModelInfo, PlanType, RenderHistory,
+    UsageSample, Workspace,
+};
+use chrono::{DateTime, Duration, FixedOffset, Local, Utc};
+use owo_colors::{AnsiColors, OwoColorize};
+use std::path::Path;
+use std::sync::OnceLock;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Human-readable display name for a model id, falling back to the id itself
+pub fn model_display_name(model_id: &str) -> String {
+    const NAMES: &[(&str, &str)] = &[
+        ("claude-opus-4-6", "Opus 4.6"),
+        ("claude-opus-4-5", "Opus 4.5"),
+        ("claude-opus-4-1", "Opus 4.1"),
+        ("claude-opus-4", "Opus 4"),
+        ("claude-sonnet-4-6", "Sonnet 4.6"),
+        ("claude-sonnet-4-5", "Sonnet 4.5"),
+        ("claude-sonnet-4", "Sonnet 4"),
+        ("claude-haiku-4-5", "Haiku 4.5"),
+        ("claude-3-7-sonnet", "Sonnet 3.7"),
+        ("claude-3-5-sonnet", "Claude 3.5 Sonnet"),
+        ("claude-3-5-haiku", "Haiku 3.5"),
+    ];
+
+    NAMES
+        .iter()
+        .find(|(prefix, _)| model_id.starts_with(prefix))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| model_id.to_string())
+}
+
+/// Compact label for a known model id, e.g. `O4.6`
+fn model_short_name(model_id: &str) -> Option<&'static str> {
+    const NAMES: &[(&str, &str)] = &[
+        ("claude-opus-4-6", "O4.6"),
+        ("claude-opus-4-5", "O4.5"),
+        ("claude-opus-4-1", "O4.1"),
+        ("claude-opus-4", "O4"),
+        ("claude-sonnet-4-6", "S4.6"),
+        ("claude-sonnet-4-5", "S4.5"),
+        ("claude-sonnet-4", "S4"),
+        ("claude-haiku-4-5", "H4.5"),
+        ("claude-3-7-sonnet", "S3.7"),
+        ("claude-3-5-sonnet", "S3.5"),
+        ("claude-3-5-haiku", "H3.5"),
+    ];
+
+    NAMES
+        .iter()
+        .find(|(prefix, _)| model_id.starts_with(prefix))
+        .map(|(_, name)| *name)
+}
+
+/// Model element text for `mode`; falls back to the display name when the
+/// id is missing or, in `Short` mode, unknown, and to `fallback` when the
+/// display name is blank
+pub fn format_model_name(model: &ModelInfo, mode: ModelDisplay, fallback: &str) -> String {
+    let full = || {
+        let display_name = model
+            .display_name
+            .trim();
+        if display_name.is_empty() {
+            fallback.to_string()
+        } else {
+            display_name.replace(" context)", ")")
+        }
+    };
+    match (
+        mode,
+        model
+            .id
+            .as_deref(),
+    ) {
+        (ModelDisplay::Short, Some(id)) => model_short_name(id).map_or_else(full, str::to_string),
+        (ModelDisplay::Id, Some(id)) => id.to_string(),
+        _ => full(),
+    }
+}
+
+/// `[name] ` from the basename of the workspace directory, or `None` without
+/// a workspace or when the directory has no basename (e.g. `/`)
+pub fn format_project_prefix(workspace: Option<&Workspace>) -> Option<String> {
+    let name = Path::new(&workspace?.current_dir).file_name()?;
+    Some(format!("[{}] ", name.to_string_lossy()))
+}
+
+/// Whether a value shown as a whole percentage survives `hide_zero`
+fn percent_shown(percent: f64, hide_zero: bool) -> bool {
+    !hide_zero || percent as u32 > 0
+}
+
+/// Whether an API percentage, shown with `decimals`, survives `hide_zero`
+fn api_percent_shown(percent: f64, hide_zero: bool, decimals: u8) -> bool {
+    percent_shown(percent * 10f64.powi(decimals as i32), hide_zero)
+}
+
+/// Whether a value shown as currency survives `hide_zero`
+fn currency_shown(amount: f64, hide_zero: bool) -> bool {
+    !hide_zero || amount >= 0.005
+}
+
+/// Format block cost, or 5h utilization with `pct_decimals` in flat plan
+/// mode when the API reports it. With `hide_zero`, nothing for no block or a
+/// zero value.
+pub fn format_block_info(
+    block: &Block,
+    plan_mode: PlanMode,
+    api_usage: Option<&ApiUsageData>,
+    hide_zero: bool,
+    pct_decimals: u8,
+    number_format: Option<NumberFormat>,
+) -> Option<String> {
+    if !block.is_active {
+        return (!hide_zero).then(|| "No block".to_string());
+    }
+
+    let primary = match (plan_mode, api_usage) {
+        (PlanMode::Flat, Some(api)) => {
+            if !api_percent_shown(api.five_hour_percent, hide_zero, pct_decimals) {
+                return None;
+            }
+            format!(
+                "5h:{}%",
+                format_percent(api.five_hour_percent, pct_decimals)
+            )
+        }
+        _ => {
+            if !currency_shown(block.cost_usd, hide_zero) {
+                return None;
+            }
+            format_currency(block.cost_usd, number_format)
+        }
+    };
+    Some(with_block_markers(block, primary))
+}
+
+/// Block cost next to the 5h utilization, e.g. `$3.42 / 40% used`. `None`
+/// without API data or an active block.
+pub fn format_cost_and_limit(
+    block: &Block,
+    api_usage: Option<&ApiUsageData>,
+    pct_decimals: u8,
+    number_format: Option<NumberFormat>,
+) -> Option<String> {
+    let api = api_usage?;
+    if !block.is_active {
+        return None;
+    }
+
+    Some(with_block_markers(
+        block,
+        format!(
+            "{} / {}% used",
+            format_currency(block.cost_usd, number_format),
+            format_percent(api.five_hour_percent, pct_decimals)
+        ),
+    ))
+}
+
+/// Append the stale or grace period marker of `block` to `text`
+fn with_block_markers(block: &Block, text: String) -> String {
+    if block.stale {
+        format!("{} ⚠️stale", text)
+    } else if block.in_grace {
+        format!("{}⏳", text)
+    } else {
+        text
+    }
+}
+
+/// Increase since the previous render as ` (+$0.07)`; nothing for
+/// decreases (e.g. a new block) or changes below half a cent
+pub fn format_cost_delta(
+    previous: Option<f64>,
+    current: f64,
+    number_format: Option<NumberFormat>,
+) -> Option<String> {
+    let delta = current - previous?;
+    (delta >= 0.005).then(|| format!(" (+{})", format_currency(delta, number_format)))
+}
+
+/// Format block cost per million tokens, counting cache tokens when
+/// `include_cache` is set
+pub fn format_effective_rate(
+    block: &Block,
+    include_cache: bool,
+    number_format: Option<NumberFormat>,
+) -> Option<String> {
+    let tokens = block.display_tokens(include_cache);
+    if !block.is_active || tokens == 0 {
+        return None;
+    }
+
+    let per_mtok = block.cost_usd / tokens as f64 * 1_000_000.0;
+    Some(format!(
+        "💲{}/Mtok",
+        format_currency(per_mtok, number_format)
+    ))
+}
+
+/// Debug segment naming the project of the block's newest entry, e.g.
+/// `[proj:my-repo]`
+pub fn format_data_source(block: &Block, home: Option<&Path>) -> Option<String> {
+    let project = block
+        .latest_source
+        .as_deref()?
+        .parent()?
+        .file_name()?
+        .to_string_lossy();
+    Some(format!("[proj:{}]", short_project_name(&project, home)))
+}
+
+/// Claude Code names project dirs after the working directory with every
+/// non-alphanumeric character replaced by `-`; drop the encoded home prefix
+fn short_project_name(project_dir: &str, home: Option<&Path>) -> String {
+    let encoded_home = home.map(|home| {
+        home.to_string_lossy()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "-")
+    });
+    encoded_home
+        .and_then(|prefix| {
+            project_dir
+                .strip_prefix(prefix.as_str())?
+                .strip_prefix('-')
+                .filter(|rest| !rest.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| project_dir.to_string())
+}
+
+/// Format number of sessions feeding the active block
+pub fn format_sessions(block: &Block) -> Option<String> {
+    if !block.is_active {
+        return None;
+    }
+
+    Some(format!("👥{}", block.session_count))
+}
+
+/// Format the current session's cost as `🧾$1.23`. With `hide_zero`, nothing
+/// for a zero cost.
+pub fn format_session_cost(
+    cost_usd: f64,
+    hide_zero: bool,
+    number_format: Option<NumberFormat>,
+) -> Option<String> {
+    currency_shown(cost_usd, hide_zero)
+        .then(|| format!("🧾{}", format_currency(cost_usd, number_format)))
+}
+
+/// Convert to the configured display timezone: `utc`, a fixed offset such as
+/// `+02:00`, or local time for `local`, unset, or unparseable values
+fn to_display_timezone(time: DateTime<Utc>, timezone: Option<&str>) -> DateTime<FixedOffset> {
+    match timezone.map(str::trim) {
+        Some(tz) if tz.eq_ignore_ascii_case("utc") => time.fixed_offset(),
+        Some(tz) => match tz.parse::<FixedOffset>() {
+            Ok(offset) => time.with_timezone(&offset),
+            Err(_) => time
+                .with_timezone(&Local)
+                .fixed_offset(),
+        },
+        None => time
+            .with_timezone(&Local)
+            .fixed_offset(),
+    }
+}
+
+/// Format a wall-clock time as `14:00` or `2:00pm`
+pub fn format_clock_time(time: DateTime<Utc>, timezone: Option<&str>, clock_24h: bool) -> String {
+    let local = to_display_timezone(time, timezone);
+    if clock_24h {
+        local
+            .format("%H:%M")
+            .to_string()
+    } else {
+        local
+            .format("%-I:%M%P")
+            .to_string()
+    }
+}
 
-/// Format block cost
-pub fn format_block_info(block: &Block) -> String {
+/// Format a reset date as `Nov 5` in the display timezone
+pub fn format_reset_date(time: DateTime<Utc>, timezone: Option<&str>) -> String {
+    to_display_timezone(time, timezone)
+        .format("%b %-d")
+        .to_string()
+}
+
+/// Format the active block's wall-clock window: `🕐14:00–19:00`
+pub fn format_block_window(
+    block: &Block,
+    timezone: Option<&str>,
+    clock_24h: bool,
+) -> Option<String> {
     if !block.is_active {
-        return "No block".to_string();
+        return None;
     }
 
-    format_currency(block.cost_usd)
+    Some(format!(
+        "🕐{}–{}",
+        format_clock_time(block.start_time, timezone, clock_24h),
+        format_clock_time(block.end_time, timezone, clock_24h)
+    ))
 }
 
 /// Pick clock emoji based on hours remaining
@@ -23,11 +319,14 @@ fn get_clock_emoji(remaining_hours: f64) -> &'static str {
     CLOCKS[idx]
 }
 
-/// Format 5-hour time remaining (subscription only)
+/// Format 5-hour time remaining (subscription only), led by a clock emoji
+/// when `show_clock`
 pub fn format_time_remaining_5h(
     block: &Block,
     api_usage: Option<&ApiUsageData>,
     plan_type: PlanType,
+    thresholds: &Thresholds,
+    show_clock: bool,
 ) -> Option<String> {
     if matches!(plan_type, PlanType::Api) || !block.is_active {
         return None;
@@ -47,14 +346,107 @@ pub fn format_time_remaining_5h(
             .hours_remaining
             .unwrap_or(0.0)
     };
+    // A reset time behind the local clock (skew) means the window is over
+    let remaining_hours = remaining_hours.max(0.0);
+
+    let text = format_hours_remaining(remaining_hours, show_clock);
+    if remaining_hours * 60.0 >= thresholds.time_warn_minutes as f64 {
+        return Some(text);
+    }
+
+    let text = if no_color() {
+        text
+    } else {
+        text.red()
+            .to_string()
+    };
+    if thresholds.time_warn_marker {
+        Some(format!("⏰{}", text))
+    } else {
+        Some(text)
+    }
+}
+
+static COLOR_OVERRIDE: OnceLock<bool> = OnceLock::new();
+
+/// Force colors on or off for this process (from `--color`), taking
+/// precedence over `NO_COLOR`. Set once at startup, before rendering.
+pub fn set_color_override(enabled: bool) {
+    let _ = COLOR_OVERRIDE.set(enabled);
+}
+
+/// Whether colors were explicitly turned off with `set_color_override`
+pub fn colors_forced_off() -> bool {
+    COLOR_OVERRIDE.get() == Some(&false)
+}
+
+/// Whether the user asked for no color via `--color` or `NO_COLOR`
+/// (https://no-color.org)
+fn no_color() -> bool {
+    match COLOR_OVERRIDE.get() {
+        Some(enabled) => !enabled,
+        None => std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+    }
+}
+
+static MULTILINE: OnceLock<bool> = OnceLock::new();
+
+/// Render each segment on its own line for this process (from
+/// `--multiline`)
+pub fn set_multiline(enabled: bool) {
+    let _ = MULTILINE.set(enabled);
+}
+
+/// Whether segments go on separate lines (see `set_multiline`)
+pub fn multiline() -> bool {
+    MULTILINE
+        .get()
+        .copied()
+        .unwrap_or(false)
+}
+
+/// `text` colored green, yellow from `api_warn_pct` and red from
+/// `api_critical_pct`; left plain with colors turned off
+fn color_api_percent(text: String, percent: f64, thresholds: &Thresholds) -> String {
+    if no_color() {
+        return text;
+    }
+
+    if percent >= thresholds.api_critical_pct as f64 {
+        text.red()
+            .to_string()
+    } else if percent >= thresholds.api_warn_pct as f64 {
+        text.yellow()
+            .to_string()
+    } else {
+        text.green()
+            .to_string()
+    }
+}
 
-    Some(format_hours_remaining(remaining_hours))
+/// Format a percentage truncated (not rounded) to `decimals`, so a value
+/// just under 100 never shows as `100`
+fn format_percent(percent: f64, decimals: u8) -> String {
+    let scale = 10f64.powi(decimals as i32);
+    format!(
+        "{:.*}",
+        decimals as usize,
+        // Nudge past float error so 40.3 stays 40.3 rather than 40.2
+        ((percent * scale) + 1e-9).floor() / scale
+    )
+}
+
+/// Join rendered segments with ` │ `, or one per line when `multiline`
+pub fn join_segments(parts: &[String], multiline: bool) -> String {
+    parts.join(if multiline { "\n" } else { " │ " })
 }
 
 /// Format 7-day time remaining (subscription only)
 pub fn format_time_remaining_7d(
     api_usage: Option<&ApiUsageData>,
     plan_type: PlanType,
+    reset_display: ResetDisplay,
+    timezone: Option<&str>,
 ) -> Option<String> {
     if matches!(plan_type, PlanType::Api) {
         return None;
@@ -65,22 +457,38 @@ pub fn format_time_remaining_7d(
     if let Some(api) = api_usage
         && let Some(reset_time) = api.seven_day_resets_at
     {
-        let remaining_hours = (reset_time - now).num_seconds() as f64 / 3600.0;
-        Some(format_days_remaining(remaining_hours))
+        let remaining_hours = ((reset_time - now).num_seconds() as f64 / 3600.0).max(0.0);
+        let relative = format_days_remaining(remaining_hours);
+        let date = format_reset_date(reset_time, timezone);
+        Some(match reset_display {
+            ResetDisplay::Relative => relative,
+            ResetDisplay::Absolute => format!("📅{}", date),
+            ResetDisplay::Both => format!("{} ({})", relative, date),
+        })
     } else {
         None
     }
 }
 
-/// Format hours remaining with clock emoji
-fn format_hours_remaining(remaining_hours: f64) -> String {
-    if remaining_hours <= 0.0 {
-        return format!("{}0h", get_clock_emoji(0.0));
+/// Format hours remaining, with a clock emoji when `show_clock`
+fn format_hours_remaining(remaining_hours: f64, show_clock: bool) -> String {
+    // Round to whole minutes before splitting so 1.999h renders `2h`, not `1h60m`
+    let total_mins = (remaining_hours.max(0.0) * 60.0).round() as i64;
+    let clock = if show_clock {
+        get_clock_emoji(if total_mins == 0 {
+            0.0
+        } else {
+            remaining_hours
+        })
+    } else {
+        ""
+    };
+    if total_mins == 0 {
+        return format!("{}0h", clock);
     }
 
-    let hours = remaining_hours.floor() as i64;
-    let mins = ((remaining_hours - hours as f64) * 60.0).round() as i64;
-    let clock = get_clock_emoji(remaining_hours);
+    let hours = total_mins / 60;
+    let mins = total_mins % 60;
 
     if hours > 0 && mins > 0 {
         format!("{}{}h{}m", clock, hours, mins)
@@ -133,14 +541,95 @@ fn format_eta(duration: Duration) -> String {
     }
 }
 
+/// 5h utilization change between two samples, in percentage points per hour
+pub fn usage_velocity(prev: UsageSample, last: UsageSample) -> Option<f64> {
+    let elapsed_secs = last.at_secs - prev.at_secs;
+    if elapsed_secs <= 0 {
+        return None;
+    }
+    Some((last.percent - prev.percent) * 3600.0 / elapsed_secs as f64)
+}
+
+/// Estimated time until 5h utilization hits 100%: `🎯~1h50m`. Needs two
+/// distinct samples with rising usage, and is hidden when the window resets
+/// before the limit would be reached. The rise is measured up to `now`, so
+/// the estimate grows while usage stays flat.
+pub fn format_limit_eta(
+    api_usage: Option<&ApiUsageData>,
+    history: &RenderHistory,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let api = api_usage?;
+    let last = history.usage_sample?;
+    let velocity = usage_velocity(
+        history.prev_usage_sample?,
+        UsageSample {
+            at_secs: last
+                .at_secs
+                .max(now.timestamp()),
+            ..last
+        },
+    )?;
+    if velocity <= 0.0 || api.five_hour_percent >= 100.0 {
+        return None;
+    }
+    let minutes = ((100.0 - api.five_hour_percent) / velocity * 60.0).round() as i64;
+    let eta = Duration::minutes(minutes);
+    if let Some(resets_at) = api.five_hour_resets_at
+        && now + eta >= resets_at
+    {
+        return None;
+    }
+    let (hours, mins) = (minutes / 60, minutes % 60);
+    if hours > 0 {
+        Some(format!("🎯~{}h{:02}m", hours, mins))
+    } else {
+        Some(format!("🎯~{}m", mins))
+    }
+}
+
+/// How the burn rate is rendered, built once from the config (see
+/// `StatuslineConfig::burn_rate_style`)
+#[derive(Debug, Clone, Copy)]
+pub struct BurnRateStyle<'a> {
+    /// Show the rate itself
+    pub show_rate: bool,
+    /// Show the time until the limit (subscriptions only)
+    pub show_eta: bool,
+    /// Drop a rate that displays as zero
+    pub hide_zero: bool,
+    /// Project the API cost over a block of this many hours instead of per
+    /// hour, shown as `$7.50/5h` in place of `burn_rate_unit`
+    pub per_block_hours: Option<i64>,
+    /// Separators of the API cost
+    pub number_format: Option<NumberFormat>,
+    /// Colors and glyphs of the rate
+    pub thresholds: &'a Thresholds,
+}
+
 /// Unified entry point for all burn rate display modes
 pub fn format_burn_rate_component(
     burn_rate: &BurnRate,
     plan_type: PlanType,
-    show_rate: bool,
-    show_eta: bool,
-    thresholds: &Thresholds,
+    style: &BurnRateStyle,
 ) -> Option<String> {
+    let BurnRateStyle {
+        show_rate,
+        show_eta,
+        hide_zero,
+        per_block_hours,
+        thresholds,
+        ..
+    } = *style;
+    let rate_shown = burn_rate.is_at_limit
+        || match plan_type {
+            PlanType::Api => currency_shown(
+                burn_rate.cost_per_hour * per_block_hours.unwrap_or(1) as f64,
+                hide_zero,
+            ),
+            PlanType::Subscription => !hide_zero || (burn_rate.ratio * 100.0).round() as i32 != 0,
+        };
+    let show_rate = show_rate && rate_shown;
     if !show_rate && !show_eta {
         return None;
     }
@@ -148,7 +637,7 @@ pub fn format_burn_rate_component(
     let eta = show_eta && matches!(plan_type, PlanType::Subscription);
 
     if show_rate {
-        Some(format_rate_display(burn_rate, plan_type, eta, thresholds))
+        Some(format_rate_display(burn_rate, plan_type, eta, style))
     } else if eta {
         format_eta_only(burn_rate, thresholds)
     } else {
@@ -161,15 +650,28 @@ fn format_rate_display(
     burn_rate: &BurnRate,
     plan_type: PlanType,
     show_eta: bool,
-    thresholds: &Thresholds,
+    style: &BurnRateStyle,
 ) -> String {
+    let thresholds = style.thresholds;
+    let number_format = style.number_format;
     if burn_rate.is_at_limit {
-        return "🔥limit".to_string();
+        return format!("{}limit", thresholds.burn_emoji_critical);
     }
 
-    let rate_str = match plan_type {
-        PlanType::Api => format!("{}/h", format_currency(burn_rate.cost_per_hour)),
-        PlanType::Subscription => format!("{}%", (burn_rate.ratio * 100.0).round() as i32),
+    let rate_str = match (plan_type, style.per_block_hours) {
+        (PlanType::Api, Some(hours)) => format!(
+            "{}/{}h",
+            format_currency(burn_rate.cost_per_hour * hours as f64, number_format),
+            hours
+        ),
+        (PlanType::Api, None) => format!(
+            "{}{}",
+            format_currency(burn_rate.cost_per_hour, number_format),
+            thresholds.burn_rate_unit
+        ),
+        (PlanType::Subscription, _) => {
+            format!("{}%", (burn_rate.ratio * 100.0).round() as i32)
+        }
     };
 
     let colored_rate = if burn_rate.ratio >= thresholds.burn_rate_danger_ratio() {
@@ -225,8 +727,12 @@ fn format_rate_display(
     };
 
     format!(
-        "🔥\u{200B}{}{}{}{}",
-        colored_rate, primary_eta, limit_str, seven_day_suffix
+        "{}\u{200B}{}{}{}{}",
+        thresholds.burn_emoji(burn_rate.ratio),
+        colored_rate,
+        primary_eta,
+        limit_str,
+        seven_day_suffix
     )
 }
 
@@ -282,152 +788,1736 @@ fn format_eta_only(burn_rate: &BurnRate, thresholds: &Thresholds) -> Option<Stri
         return None;
     }
 
-    Some(format!(
-        "⏱\u{200B}{}{}{}",
-        primary.unwrap_or_default(),
-        limit_str,
-        secondary.unwrap_or_default()
-    ))
-}
-
-/// Format context information
-pub fn format_context(context: Option<&ContextInfo>, thresholds: &Thresholds) -> String {
-    match context {
-        Some(info) => {
-            let color = if info.percentage < thresholds.context_warning {
-                info.percentage
-                    .to_string()
-                    .green()
-                    .to_string()
-            } else if info.percentage < thresholds.context_danger {
-                info.percentage
-                    .to_string()
-                    .yellow()
-                    .to_string()
-            } else {
-                info.percentage
-                    .to_string()
-                    .red()
-                    .to_string()
-            };
+    Some(format!(
+        "⏱\u{200B}{}{}{}",
+        primary.unwrap_or_default(),
+        limit_str,
+        secondary.unwrap_or_default()
+    ))
+}
+
+/// Compact token count with one decimal: `999`, `95.5k`, `1.2M`
+pub fn format_tokens_compact(tokens: u64) -> String {
+    if tokens < 1000 {
+        return tokens.to_string();
+    }
+    // Values that would round to 1000.0k switch to M
+    if tokens < 999_950 {
+        format!("{:.1}k", tokens as f64 / 1e3)
+    } else {
+        format!("{:.1}M", tokens as f64 / 1e6)
+    }
+}
+
+/// Context usage as `95k(47%)`, or `95.5k(47%)` with `compact` tokens.
+/// With `show_remaining`, also the tokens left before auto-compaction, as
+/// `95k(47%, 60k left)`.
+pub fn format_context(
+    context: Option<&ContextInfo>,
+    thresholds: &Thresholds,
+    compact: bool,
+    show_remaining: bool,
+) -> String {
+    match context {
+        Some(info) => {
+            let color = if info.percentage < thresholds.context_warning {
+                info.percentage
+                    .to_string()
+                    .green()
+                    .to_string()
+            } else if info.percentage < thresholds.context_danger {
+                info.percentage
+                    .to_string()
+                    .yellow()
+                    .to_string()
+            } else {
+                info.percentage
+                    .to_string()
+                    .red()
+                    .to_string()
+            };
+
+            let tokens = |count: u64| {
+                if compact {
+                    format_tokens_compact(count)
+                } else {
+                    format!("{}k", count / 1000)
+                }
+            };
+            match info
+                .remaining
+                .filter(|_| show_remaining)
+            {
+                Some(remaining) => format!(
+                    "{}({}%, {} left)",
+                    tokens(info.tokens),
+                    color,
+                    tokens(remaining)
+                ),
+                None => format!("{}({}%)", tokens(info.tokens), color),
+            }
+        }
+        None => "N/A".to_string(),
+    }
+}
+
+/// Digit grouping and decimal separators for a locale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub group: &'static str,
+    pub decimal: &'static str,
+}
+
+impl NumberFormat {
+    pub const EN: Self = Self {
+        group: ",",
+        decimal: ".",
+    };
+
+    /// Separators for a locale such as `de`, `fr_FR` or `pt-BR`
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let language = locale
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let (group, decimal) = match language.as_str() {
+            "en" | "ja" | "zh" | "ko" | "he" | "th" => (",", "."),
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" => (".", ","),
+            "fr" | "nb" | "no" | "sv" | "fi" | "cs" | "sk" | "pl" | "ru" | "uk" => {
+                ("\u{202F}", ",")
+            }
+            _ => return None,
+        };
+        Some(Self { group, decimal })
+    }
+}
+
+/// Format a number with grouped thousands and fixed decimals
+pub fn format_number(value: f64, decimals: usize, number_format: &NumberFormat) -> String {
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut result = String::new();
+    if value < 0.0
+        && formatted
+            .chars()
+            .any(|c| c != '0' && c != '.')
+    {
+        result.push('-');
+    }
+    for (i, digit) in int_part
+        .chars()
+        .enumerate()
+    {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            result.push_str(number_format.group);
+        }
+        result.push(digit);
+    }
+    if let Some(frac) = frac_part {
+        result.push_str(number_format.decimal);
+        result.push_str(frac);
+    }
+    result
+}
+
+/// Format currency, with the separators of `number_format` when a locale is
+/// configured (see `StatuslineConfig::number_format`)
+pub fn format_currency(amount: f64, number_format: Option<NumberFormat>) -> String {
+    match number_format {
+        Some(number_format) => format!("${}", format_number(amount, 2, &number_format)),
+        None => format!("${:.2}", amount),
+    }
+}
+
+/// Map decimal portion (0.0-0.9) to Unicode block character (vertical fill)
+fn decimal_to_block(value: f64) -> char {
+    const BLOCKS: [char; 10] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█', '█'];
+    let idx = ((value.fract() * 10.0) as usize).min(9);
+    BLOCKS[idx]
+}
+
+/// Spark glyph for a percentage. `Fraction` shows the next digit after the
+/// `decimals` shown (movement below the displayed precision); `Decile` shows
+/// how far the value is into its current 10% step.
+fn spark_block(percent: f64, mode: SparkMode, decimals: u8) -> char {
+    match mode {
+        SparkMode::Fraction => decimal_to_block(percent * 10f64.powi(decimals as i32) + 1e-9),
+        SparkMode::Decile => decimal_to_block((percent % 10.0) / 10.0),
+    }
+}
+
+/// Timezone and 12h/24h setting for a wall-clock time
+#[derive(Debug, Clone, Copy)]
+pub struct ClockStyle<'a> {
+    pub timezone: Option<&'a str>,
+    pub clock_24h: bool,
+}
+
+/// Format 5h API usage, followed by the reset time as ` (→15:00)` when
+/// `reset_clock` is given and the API reported one
+pub fn format_api_usage_5h(
+    api_usage: Option<&ApiUsageData>,
+    spark_mode: SparkMode,
+    hide_zero: bool,
+    decimals: u8,
+    reset_clock: Option<ClockStyle>,
+    thresholds: &Thresholds,
+) -> Option<String> {
+    api_usage
+        .filter(|api| api_percent_shown(api.five_hour_percent, hide_zero, decimals))
+        .map(|api| {
+            let five_hour = color_api_percent(
+                format!("{}%", format_percent(api.five_hour_percent, decimals)),
+                api.five_hour_percent,
+                thresholds,
+            );
+            let five_hour_block = spark_block(api.five_hour_percent, spark_mode, decimals);
+            let usage = if five_hour_block == ' ' {
+                format!("5h:{}", five_hour)
+            } else {
+                format!("5h:{}{}", five_hour, five_hour_block)
+            };
+            match (reset_clock, api.five_hour_resets_at) {
+                (Some(clock), Some(reset)) => format!(
+                    "{} (→{})",
+                    usage,
+                    format_clock_time(reset, clock.timezone, clock.clock_24h)
+                ),
+                _ => usage,
+            }
+        })
+}
+
+/// Format 7d API usage
+pub fn format_api_usage_7d(
+    api_usage: Option<&ApiUsageData>,
+    hide_zero: bool,
+    decimals: u8,
+    thresholds: &Thresholds,
+) -> Option<String> {
+    api_usage
+        .filter(|api| api_percent_shown(api.seven_day_percent, hide_zero, decimals))
+        .map(|api| {
+            let seven_day = color_api_percent(
+                format!("{}%", format_percent(api.seven_day_percent, decimals)),
+                api.seven_day_percent,
+                thresholds,
+            );
+            format!("7d:{}", seven_day)
+        })
+}
+
+/// Format Sonnet 7d API usage. When the API reported no Sonnet limit,
+/// `when_absent` decides between nothing, `S7d:0%` and `S7d:-`.
+pub fn format_api_usage_sonnet(
+    api_usage: Option<&ApiUsageData>,
+    hide_zero: bool,
+    decimals: u8,
+    when_absent: SonnetWhenAbsent,
+) -> Option<String> {
+    let api = api_usage?;
+    let percent = match (api.seven_day_sonnet_percent, when_absent) {
+        (Some(percent), _) => percent,
+        (None, SonnetWhenAbsent::Hide) => return None,
+        (None, SonnetWhenAbsent::ShowZero) => 0.0,
+        (None, SonnetWhenAbsent::ShowDash) => return Some("S7d:-".to_string()),
+    };
+    api_percent_shown(percent, hide_zero, decimals)
+        .then(|| format!("S7d:{}%", format_percent(percent, decimals)))
+}
+
+/// Format Opus 7d API usage, omitted when the API reported no Opus limit
+pub fn format_api_usage_opus(
+    api_usage: Option<&ApiUsageData>,
+    hide_zero: bool,
+    decimals: u8,
+) -> Option<String> {
+    api_usage
+        .and_then(|api| api.seven_day_opus_percent)
+        .filter(|percent| api_percent_shown(*percent, hide_zero, decimals))
+        .map(|percent| format!("O7d:{}%", format_percent(percent, decimals)))
+}
+
+/// How the API metrics are rendered, built once from the config (see
+/// `StatuslineConfig::api_metric_style`)
+#[derive(Debug, Clone, Copy)]
+pub struct ApiMetricStyle<'a> {
+    /// Glyph after the 5h percentage
+    pub spark_mode: SparkMode,
+    /// Drop limits that display as zero
+    pub hide_zero: bool,
+    /// Decimals shown in each percentage
+    pub pct_decimals: u8,
+    /// Reset time after the 5h percentage
+    pub reset_clock: Option<ClockStyle<'a>>,
+    /// List each account's value instead of the combined maximum
+    pub per_account: bool,
+    /// What the Sonnet limit shows when the API omits it
+    pub sonnet_absent: SonnetWhenAbsent,
+    /// Colors of the 5h and 7d percentages
+    pub thresholds: &'a Thresholds,
+}
+
+/// Format one API limit in `style`. With `per_account` and several
+/// accounts, lists each account's value as `5h:A40%/B10%`.
+pub fn format_api_metric(
+    api_usage: Option<&ApiUsageData>,
+    metric: ApiMetric,
+    style: &ApiMetricStyle,
+) -> Option<String> {
+    if style.per_account
+        && let Some(api) = api_usage
+        && !api
+            .accounts
+            .is_empty()
+    {
+        let single = ApiMetricStyle {
+            per_account: false,
+            ..*style
+        };
+        let mut name = None;
+        let values: Vec<String> = api
+            .accounts
+            .iter()
+            .filter_map(|(label, account)| {
+                let piece = format_api_metric(Some(account), metric, &single)?;
+                let (metric_name, value) = piece.split_once(':')?;
+                name.get_or_insert_with(|| metric_name.to_string());
+                Some(format!("{}{}", label, value))
+            })
+            .collect();
+        return name.map(|name| format!("{}:{}", name, values.join("/")));
+    }
+
+    let hide_zero = style.hide_zero;
+    let decimals = style.pct_decimals;
+    match metric {
+        ApiMetric::FiveHour => format_api_usage_5h(
+            api_usage,
+            style.spark_mode,
+            hide_zero,
+            decimals,
+            style.reset_clock,
+            style.thresholds,
+        ),
+        ApiMetric::SevenDay => {
+            format_api_usage_7d(api_usage, hide_zero, decimals, style.thresholds)
+        }
+        ApiMetric::SevenDaySonnet => {
+            format_api_usage_sonnet(api_usage, hide_zero, decimals, style.sonnet_absent)
+        }
+        ApiMetric::SevenDayOpus => format_api_usage_opus(api_usage, hide_zero, decimals),
+    }
+}
+
+/// Format the shown API limits as one piece, e.g. `5h:40%▅` and `7d:12%`
+/// become `5h40%▅·7d12%`. The 5h reset time is never shown here.
+pub fn format_api_usage_compact(
+    api_usage: Option<&ApiUsageData>,
+    metrics: &[ApiMetric],
+    style: &ApiMetricStyle,
+) -> Option<String> {
+    let style = ApiMetricStyle {
+        reset_clock: None,
+        ..*style
+    };
+    let pieces: Vec<String> = metrics
+        .iter()
+        .filter_map(|metric| format_api_metric(api_usage, *metric, &style))
+        .map(|piece| piece.replacen(':', "", 1))
+        .collect();
+
+    (!pieces.is_empty()).then(|| pieces.join("·"))
+}
+
+/// Format the update notice as `🔼2.1.0`, or `🔼2.0.71→2.1.0` when `current`
+/// is given
+pub fn format_update(prefix: &str, current: Option<&str>, latest: &str) -> String {
+    match current {
+        Some(current) => format!("{}{}→{}", prefix, current, latest),
+        None => format!("{}{}", prefix, latest),
+    }
+}
+
+/// Format OAuth token expiry warning when within `warning_hours` of expiring
+pub fn format_token_expiry(
+    expires_at: Option<DateTime<Utc>>,
+    warning_hours: u32,
+) -> Option<String> {
+    let remaining = expires_at? - Utc::now();
+    if remaining <= Duration::zero() {
+        Some("🔑token expired".to_string())
+    } else if remaining <= Duration::hours(warning_hours as i64) {
+        Some("🔑token expiring".to_string())
+    } else {
+        None
+    }
+}
+
+/// Whether any API limit is at or over 100%
+fn is_api_quota_exhausted(api_usage: Option<&ApiUsageData>) -> bool {
+    api_usage.is_some_and(|api| api.five_hour_percent >= 100.0 || api.seven_day_percent >= 100.0)
+}
+
+/// Prefix the 📊 segment with 🛑 and color it red when a limit is exhausted,
+/// replacing the colors of the individual percentages
+pub fn format_api_segment(segment: String, api_usage: Option<&ApiUsageData>) -> String {
+    if is_api_quota_exhausted(api_usage) {
+        format!("🛑{}", strip_ansi(&segment).red())
+    } else {
+        segment
+    }
+}
+
+/// Remove ANSI escape sequences such as `\x1b[31m`
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end at the first byte in `@`..=`~`
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Cut each line of `s` to `max_width` visible columns, ending cut lines
+/// with `…`. ANSI escape codes don't count towards the width and are all
+/// kept, so colors opened before the cut are still reset.
+pub fn truncate_visible(s: &str, max_width: usize) -> String {
+    s.split('\n')
+        .map(|line| truncate_line(line, max_width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn truncate_line(line: &str, max_width: usize) -> String {
+    if UnicodeWidthStr::width(strip_ansi(line).as_str()) <= max_width {
+        return line.to_string();
+    }
+
+    let budget = max_width.saturating_sub(1);
+    let mut out = String::with_capacity(line.len());
+    let mut width = 0;
+    let mut cut = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            if let Some(next) = chars.next() {
+                out.push(next);
+                if next == '[' {
+                    for c in chars.by_ref() {
+                        out.push(c);
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+            }
+            continue;
+        }
+        if cut {
+            continue;
+        }
+        let char_width = UnicodeWidthChar::width(c).unwrap_or(0);
+        if width + char_width > budget {
+            out.push('…');
+            cut = true;
+            continue;
+        }
+        width += char_width;
+        out.push(c);
+    }
+    out
+}
+
+pub fn strip_emojis(s: &str) -> String {
+    s.chars()
+        .filter(|c| {
+            let cp = *c as u32;
+            !(0x1F300..=0x1FAFF).contains(&cp) && cp != 0x200B && cp != 0x23F0
+        })
+        .collect()
+}
+
+/// Parse a color name (`cyan`, `bright_blue`, ...) into an ANSI color
+pub fn parse_color(name: &str) -> Option<AnsiColors> {
+    let normalized = name
+        .trim()
+        .to_ascii_lowercase()
+        .replace(['-', ' '], "_");
+    let color = match normalized.as_str() {
+        "black" => AnsiColors::Black,
+        "red" => AnsiColors::Red,
+        "green" => AnsiColors::Green,
+        "yellow" => AnsiColors::Yellow,
+        "blue" => AnsiColors::Blue,
+        "magenta" | "purple" => AnsiColors::Magenta,
+        "cyan" => AnsiColors::Cyan,
+        "white" => AnsiColors::White,
+        "default" => AnsiColors::Default,
+        "bright_black" | "gray" | "grey" => AnsiColors::BrightBlack,
+        "bright_red" => AnsiColors::BrightRed,
+        "bright_green" => AnsiColors::BrightGreen,
+        "bright_yellow" => AnsiColors::BrightYellow,
+        "bright_blue" => AnsiColors::BrightBlue,
+        "bright_magenta" => AnsiColors::BrightMagenta,
+        "bright_cyan" => AnsiColors::BrightCyan,
+        "bright_white" => AnsiColors::BrightWhite,
+        _ => return None,
+    };
+    Some(color)
+}
+
+/// Format directory path with home replacement and color
+pub fn format_directory(path: &Path, color: AnsiColors) -> String {
+    let formatted = match crate::paths::home_dir() {
+        Ok(home) => replace_home(path, &home),
+        Err(_) => path
+            .display()
+            .to_string(),
+    };
+
+    formatted
+        .color(color)
+        .to_string()
+}
+
+/// Replace a leading `home` with `~`, matching whole path components so
+/// non-UTF-8 bytes are only made lossy at display time
+fn replace_home(path: &Path, home: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(rest)
+            if rest
+                .as_os_str()
+                .is_empty() =>
+        {
+            "~".to_string()
+        }
+        Ok(rest) => Path::new("~")
+            .join(rest)
+            .display()
+            .to_string(),
+        Err(_) => path
+            .display()
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_METRICS: [ApiMetric; 4] = [
+        ApiMetric::FiveHour,
+        ApiMetric::SevenDay,
+        ApiMetric::SevenDaySonnet,
+        ApiMetric::SevenDayOpus,
+    ];
+
+    fn sample(percent: f64, at_secs: i64) -> UsageSample {
+        UsageSample { percent, at_secs }
+    }
+
+    #[test]
+    fn test_usage_velocity() {
+        // 10 points over 30 minutes = 20%/h
+        assert_eq!(
+            usage_velocity(sample(40.0, 1_000), sample(50.0, 2_800)),
+            Some(20.0)
+        );
+        assert_eq!(
+            usage_velocity(sample(50.0, 1_000), sample(40.0, 2_800)),
+            Some(-20.0)
+        );
+        assert_eq!(
+            usage_velocity(sample(40.0, 1_000), sample(50.0, 1_000)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_record_usage_keeps_distinct_samples() {
+        let mut history = RenderHistory::default();
+        history.record_usage(40.0, 100);
+        history.record_usage(40.0, 200);
+        assert_eq!(history.usage_sample, Some(sample(40.0, 100)));
+        assert_eq!(history.prev_usage_sample, None);
+        history.record_usage(45.0, 300);
+        assert_eq!(history.prev_usage_sample, Some(sample(40.0, 100)));
+        assert_eq!(history.usage_sample, Some(sample(45.0, 300)));
+    }
+
+    #[test]
+    fn test_format_limit_eta_decays_while_flat() {
+        let api = ApiUsageData {
+            five_hour_percent: 50.0,
+            five_hour_resets_at: None,
+            seven_day_percent: 0.0,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+        let mut history = RenderHistory::default();
+        history.record_usage(40.0, 1_000);
+        history.record_usage(50.0, 2_800);
+        // Still 50% half an hour later: 10%/h over the whole hour
+        history.record_usage(50.0, 4_600);
+        let later = DateTime::from_timestamp(4_600, 0).unwrap();
+        assert_eq!(
+            format_limit_eta(Some(&api), &history, later),
+            Some("🎯~5h00m".to_string())
+        );
+
+        // Flat until the window resets first
+        let resets = ApiUsageData {
+            five_hour_resets_at: Some(later + Duration::hours(3)),
+            ..api
+        };
+        assert_eq!(format_limit_eta(Some(&resets), &history, later), None);
+    }
+
+    #[test]
+    fn test_format_limit_eta() {
+        let api = ApiUsageData {
+            five_hour_percent: 50.0,
+            five_hour_resets_at: None,
+            seven_day_percent: 0.0,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+        let now = DateTime::from_timestamp(2_800, 0).unwrap();
+        let mut history = RenderHistory::default();
+        assert_eq!(format_limit_eta(Some(&api), &history, now), None);
+
+        // 20%/h with 50% left: 2h30m
+        history.prev_usage_sample = Some(sample(40.0, 1_000));
+        history.usage_sample = Some(sample(50.0, 2_800));
+        assert_eq!(
+            format_limit_eta(Some(&api), &history, now),
+            Some("🎯~2h30m".to_string())
+        );
+
+        let fast = ApiUsageData {
+            five_hour_percent: 95.0,
+            ..api.clone()
+        };
+        assert_eq!(
+            format_limit_eta(Some(&fast), &history, now),
+            Some("🎯~15m".to_string())
+        );
+
+        // Resets before the limit would be reached
+        let resets_soon = ApiUsageData {
+            five_hour_resets_at: Some(now + Duration::hours(1)),
+            ..api.clone()
+        };
+        assert_eq!(format_limit_eta(Some(&resets_soon), &history, now), None);
+
+        // Falling usage
+        history.usage_sample = Some(sample(30.0, 2_800));
+        assert_eq!(format_limit_eta(Some(&api), &history, now), None);
+        assert_eq!(format_limit_eta(None, &history, now), None);
+    }
+
+    #[test]
+    fn test_decimal_to_block_zero() {
+        assert_eq!(decimal_to_block(0.0), ' ');
+        assert_eq!(decimal_to_block(50.0), ' ');
+    }
+
+    #[test]
+    fn test_spark_block_modes() {
+        assert_eq!(spark_block(50.0, SparkMode::Fraction, 0), ' ');
+        assert_eq!(spark_block(50.5, SparkMode::Fraction, 0), '▅');
+        assert_eq!(spark_block(50.9, SparkMode::Fraction, 0), '█');
+
+        assert_eq!(spark_block(50.0, SparkMode::Decile, 0), ' ');
+        assert_eq!(spark_block(50.5, SparkMode::Decile, 0), ' ');
+        assert_eq!(spark_block(50.9, SparkMode::Decile, 0), ' ');
+        assert_eq!(spark_block(55.0, SparkMode::Decile, 0), '▅');
+        assert_eq!(spark_block(59.9, SparkMode::Decile, 0), '█');
+    }
+
+    #[test]
+    fn test_api_percent_severity_colors() {
+        let thresholds = Thresholds {
+            api_warn_pct: 70,
+            api_critical_pct: 90,
+            ..Thresholds::default()
+        };
+        let color = |percent: f64| color_api_percent("x".to_string(), percent, &thresholds);
+
+        assert_eq!(
+            color(0.0),
+            "x".green()
+                .to_string()
+        );
+        assert_eq!(
+            color(69.9),
+            "x".green()
+                .to_string()
+        );
+        assert_eq!(
+            color(70.0),
+            "x".yellow()
+                .to_string()
+        );
+        assert_eq!(
+            color(89.9),
+            "x".yellow()
+                .to_string()
+        );
+        assert_eq!(
+            color(90.0),
+            "x".red()
+                .to_string()
+        );
+        assert_eq!(
+            color(120.0),
+            "x".red()
+                .to_string()
+        );
+
+        // Only the percentage is colored, not the label or spark
+        let api = api_usage(75.5, 95.0);
+        assert_eq!(
+            format_api_usage_5h(Some(&api), SparkMode::Fraction, false, 0, None, &thresholds),
+            Some(format!("5h:{}▅", "75%".yellow()))
+        );
+        assert_eq!(
+            format_api_usage_7d(Some(&api), false, 0, &thresholds),
+            Some(format!("7d:{}", "95%".red()))
+        );
+        assert_eq!(
+            format_api_metric(Some(&api), ApiMetric::SevenDay, &api_style(&thresholds)),
+            Some(format!("7d:{}", "95%".red()))
+        );
+    }
+
+    #[test]
+    fn test_api_percent_decimals() {
+        assert_eq!(format_percent(40.37, 0), "40");
+        assert_eq!(format_percent(40.0, 0), "40");
+        assert_eq!(format_percent(40.37, 1), "40.3");
+        assert_eq!(format_percent(40.3, 1), "40.3");
+        assert_eq!(format_percent(40.0, 1), "40.0");
+        // Truncated, so not-quite-exhausted never reads as 100
+        assert_eq!(format_percent(99.96, 1), "99.9");
+
+        // The spark shows the digit after the displayed ones
+        assert_eq!(spark_block(40.37, SparkMode::Fraction, 0), '▃');
+        assert_eq!(spark_block(40.37, SparkMode::Fraction, 1), '▇');
+        assert_eq!(spark_block(40.3, SparkMode::Fraction, 1), ' ');
+        assert_eq!(spark_block(45.0, SparkMode::Decile, 1), '▅');
+    }
+
+    #[test]
+    fn test_api_metric_decimals_per_style() {
+        let thresholds = Thresholds::default();
+        let api = api_usage(40.37, 12.5);
+        let style = ApiMetricStyle {
+            pct_decimals: 1,
+            ..api_style(&thresholds)
+        };
+        assert_eq!(
+            plain(format_api_metric(Some(&api), ApiMetric::SevenDay, &style)).as_deref(),
+            Some("7d:12.5%")
+        );
+        assert_eq!(
+            plain(format_api_metric(
+                Some(&api),
+                ApiMetric::SevenDay,
+                &api_style(&thresholds)
+            ))
+            .as_deref(),
+            Some("7d:12%")
+        );
+        assert_eq!(
+            format_cost_and_limit(&active_block(), Some(&api), 1, None).as_deref(),
+            Some("$0.00 / 40.3% used")
+        );
+    }
+
+    #[test]
+    fn test_decimal_to_block_fractions() {
+        assert_eq!(decimal_to_block(0.1), '▁');
+        assert_eq!(decimal_to_block(0.5), '▅');
+        assert_eq!(decimal_to_block(0.9), '█');
+    }
+
+    #[test]
+    fn test_format_api_usage_5h_no_trailing_space() {
+        let data = ApiUsageData {
+            five_hour_percent: 37.0,
+            five_hour_resets_at: None,
+            seven_day_percent: 10.0,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+        let result = strip_ansi(
+            &format_api_usage_5h(
+                Some(&data),
+                SparkMode::Fraction,
+                false,
+                0,
+                None,
+                &Thresholds::default(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(result, "5h:37%");
+        assert!(!result.ends_with(' '));
+    }
+
+    #[test]
+    fn test_format_api_usage_5h_with_block() {
+        let data = ApiUsageData {
+            five_hour_percent: 37.5,
+            five_hour_resets_at: None,
+            seven_day_percent: 10.0,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+        let result = format_api_usage_5h(
+            Some(&data),
+            SparkMode::Fraction,
+            false,
+            0,
+            None,
+            &Thresholds::default(),
+        );
+        assert_eq!(plain(result).as_deref(), Some("5h:37%▅"));
+    }
+
+    #[test]
+    fn test_format_api_usage_sonnet_absent_vs_zero() {
+        let mut data = ApiUsageData {
+            five_hour_percent: 37.0,
+            five_hour_resets_at: None,
+            seven_day_percent: 10.0,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+        assert_eq!(
+            format_api_usage_sonnet(Some(&data), false, 0, SonnetWhenAbsent::Hide),
+            None
+        );
+
+        data.seven_day_sonnet_percent = Some(0.0);
+        assert_eq!(
+            format_api_usage_sonnet(Some(&data), false, 0, SonnetWhenAbsent::Hide).as_deref(),
+            Some("S7d:0%")
+        );
+
+        data.seven_day_sonnet_percent = Some(42.7);
+        assert_eq!(
+            format_api_usage_sonnet(Some(&data), false, 0, SonnetWhenAbsent::Hide).as_deref(),
+            Some("S7d:42%")
+        );
+    }
+
+    #[test]
+    fn test_format_api_usage_sonnet_when_absent() {
+        let absent = api_usage(37.0, 10.0);
+        let sonnet = |when_absent, hide_zero| {
+            format_api_usage_sonnet(Some(&absent), hide_zero, 0, when_absent)
+        };
+
+        assert_eq!(sonnet(SonnetWhenAbsent::Hide, false), None);
+        assert_eq!(
+            sonnet(SonnetWhenAbsent::ShowZero, false).as_deref(),
+            Some("S7d:0%")
+        );
+        assert_eq!(
+            sonnet(SonnetWhenAbsent::ShowDash, false).as_deref(),
+            Some("S7d:-")
+        );
+
+        // A shown zero still follows hide_zero_segments; the dash is no zero
+        assert_eq!(sonnet(SonnetWhenAbsent::ShowZero, true), None);
+        assert_eq!(
+            sonnet(SonnetWhenAbsent::ShowDash, true).as_deref(),
+            Some("S7d:-")
+        );
+
+        // A reported limit is shown the same in every mode
+        let mut reported = absent.clone();
+        reported.seven_day_sonnet_percent = Some(5.0);
+        for when_absent in [
+            SonnetWhenAbsent::Hide,
+            SonnetWhenAbsent::ShowZero,
+            SonnetWhenAbsent::ShowDash,
+        ] {
+            assert_eq!(
+                format_api_usage_sonnet(Some(&reported), false, 0, when_absent).as_deref(),
+                Some("S7d:5%")
+            );
+        }
+
+        let thresholds = Thresholds::default();
+        assert_eq!(
+            plain(format_api_usage_compact(
+                Some(&absent),
+                &[ApiMetric::SevenDay, ApiMetric::SevenDaySonnet],
+                &ApiMetricStyle {
+                    sonnet_absent: SonnetWhenAbsent::ShowDash,
+                    ..api_style(&thresholds)
+                }
+            ))
+            .as_deref(),
+            Some("7d10%·S7d-")
+        );
+        assert_eq!(
+            format_api_usage_sonnet(None, false, 0, SonnetWhenAbsent::ShowDash),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format_api_usage_compact_vs_expanded() {
+        let thresholds = Thresholds::default();
+        let style = api_style(&thresholds);
+        let mut data = api_usage(40.5, 12.0);
+        data.seven_day_sonnet_percent = Some(3.0);
+        let expanded = [
+            format_api_usage_5h(
+                Some(&data),
+                SparkMode::Fraction,
+                false,
+                0,
+                None,
+                &thresholds,
+            )
+            .unwrap(),
+            format_api_usage_7d(Some(&data), false, 0, &thresholds).unwrap(),
+            format_api_usage_sonnet(Some(&data), false, 0, SonnetWhenAbsent::Hide).unwrap(),
+        ]
+        .join(" ");
+        assert_eq!(strip_ansi(&expanded), "5h:40%▅ 7d:12% S7d:3%");
+
+        let compact = format_api_usage_compact(Some(&data), &ALL_METRICS, &style).unwrap();
+        assert_eq!(strip_ansi(&compact), "5h40%▅·7d12%·S7d3%");
+        assert_eq!(compact.replace('·', " "), expanded.replace(':', ""));
+
+        assert_eq!(
+            plain(format_api_usage_compact(
+                Some(&data),
+                &[ApiMetric::SevenDay],
+                &style
+            ))
+            .as_deref(),
+            Some("7d12%")
+        );
+        assert_eq!(format_api_usage_compact(None, &ALL_METRICS, &style), None);
+
+        // The exhausted marker wraps the compact piece like the expanded one
+        let exhausted = api_usage(100.0, 12.0);
+        let segment = format_api_segment(
+            format!(
+                "📊{}",
+                format_api_usage_compact(
+                    Some(&exhausted),
+                    &[ApiMetric::FiveHour, ApiMetric::SevenDay],
+                    &style
+                )
+                .unwrap()
+            ),
+            Some(&exhausted),
+        );
+        // All red: the green 7d percentage does not end the red early
+        assert_eq!(segment, format!("🛑{}", "📊5h100%·7d12%".red()));
+    }
+
+    #[test]
+    fn test_format_api_usage_compact_follows_metric_order() {
+        let mut data = api_usage(40.5, 12.0);
+        data.seven_day_opus_percent = Some(8.0);
+        assert_eq!(
+            plain(format_api_usage_compact(
+                Some(&data),
+                &[ApiMetric::SevenDayOpus, ApiMetric::FiveHour],
+                &api_style(&Thresholds::default())
+            ))
+            .as_deref(),
+            Some("O7d8%·5h40%▅")
+        );
+    }
+
+    #[test]
+    fn test_format_api_metric_per_account() {
+        let mut combined = api_usage(40.0, 30.0);
+        combined.accounts = vec![('A', api_usage(40.0, 12.0)), ('B', api_usage(10.0, 30.0))];
+        let thresholds = Thresholds::default();
+        let metric = |metric, per_account| {
+            plain(format_api_metric(
+                Some(&combined),
+                metric,
+                &ApiMetricStyle {
+                    per_account,
+                    ..api_style(&thresholds)
+                },
+            ))
+        };
+        assert_eq!(
+            metric(ApiMetric::FiveHour, true).as_deref(),
+            Some("5h:A40%/B10%")
+        );
+        assert_eq!(
+            metric(ApiMetric::SevenDay, true).as_deref(),
+            Some("7d:A12%/B30%")
+        );
+        assert_eq!(
+            metric(ApiMetric::FiveHour, false).as_deref(),
+            Some("5h:40%")
+        );
+        assert_eq!(metric(ApiMetric::SevenDaySonnet, true), None);
+        assert_eq!(
+            plain(format_api_usage_compact(
+                Some(&combined),
+                &[ApiMetric::FiveHour, ApiMetric::SevenDay],
+                &ApiMetricStyle {
+                    per_account: true,
+                    ..api_style(&thresholds)
+                }
+            ))
+            .as_deref(),
+            Some("5hA40%/B10%·7dA12%/B30%")
+        );
+    }
+
+    #[test]
+    fn test_format_effective_rate() {
+        let mut block = Block {
+            cost_usd: 8.42,
+            total_tokens: 2_000_000,
+            cache_read_tokens: 1_000_000,
+            ..active_block()
+        };
+        assert_eq!(
+            format_effective_rate(&block, true, None).as_deref(),
+            Some("💲$4.21/Mtok")
+        );
+        assert_eq!(
+            format_effective_rate(&block, false, None).as_deref(),
+            Some("💲$8.42/Mtok")
+        );
+
+        block.total_tokens = 0;
+        assert_eq!(format_effective_rate(&block, true, None), None);
+    }
+
+    #[test]
+    fn test_time_remaining_warning_marker() {
+        let mut block = Block {
+            start_time: Utc::now() - Duration::hours(4),
+            end_time: Utc::now() + Duration::minutes(10),
+            cost_usd: 1.0,
+            total_tokens: 1000,
+            hours_remaining: Some(10.0 / 60.0),
+            ..active_block()
+        };
+        let thresholds = Thresholds::default();
+
+        let warned =
+            format_time_remaining_5h(&block, None, PlanType::Subscription, &thresholds, true)
+                .unwrap();
+        assert!(warned.starts_with("⏰"), "got {}", warned);
+        assert!(warned.contains("10m"));
+
+        block.hours_remaining = Some(0.5);
+        let normal =
+            format_time_remaining_5h(&block, None, PlanType::Subscription, &thresholds, true)
+                .unwrap();
+        assert_eq!(normal, "🕐30m");
+    }
+
+    #[test]
+    fn test_time_remaining_without_clock() {
+        for hours in [0.0, 0.5, 2.25, 4.0] {
+            let text = format_hours_remaining(hours, false);
+            assert!(text.is_ascii(), "got {}", text);
+        }
+        assert_eq!(format_hours_remaining(2.25, false), "2h15m");
+        assert_eq!(format_hours_remaining(0.0, false), "0h");
+
+        let mut block = Block::inactive(Utc::now(), 5);
+        block.is_active = true;
+        block.hours_remaining = Some(0.5);
+        let text = format_time_remaining_5h(
+            &block,
+            None,
+            PlanType::Subscription,
+            &Thresholds::default(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(text, "30m");
+    }
+
+    #[test]
+    fn test_time_remaining_reset_in_past() {
+        let block = Block {
+            start_time: Utc::now() - Duration::hours(5),
+            end_time: Utc::now(),
+            cost_usd: 1.0,
+            total_tokens: 1000,
+            hours_remaining: Some(0.0),
+            ..active_block()
+        };
+        let mut api = api_usage(50.0, 10.0);
+        api.five_hour_resets_at = Some(Utc::now() - Duration::minutes(3));
+        api.seven_day_resets_at = Some(Utc::now() - Duration::minutes(3));
+
+        let five_hour = format_time_remaining_5h(
+            &block,
+            Some(&api),
+            PlanType::Subscription,
+            &Thresholds::default(),
+            true,
+        )
+        .unwrap();
+        assert!(five_hour.contains("🕛0h"), "got {}", five_hour);
+        assert!(!five_hour.contains('-'), "got {}", five_hour);
+
+        let seven_day = format_time_remaining_7d(
+            Some(&api),
+            PlanType::Subscription,
+            ResetDisplay::Relative,
+            None,
+        )
+        .unwrap();
+        assert_eq!(seven_day, "📅0d");
+
+        assert_eq!(format_hours_remaining(-0.25, true), "🕛0h");
+        assert_eq!(format_hours_remaining(0.001, true), "🕛0h");
+        assert_eq!(format_hours_remaining(1.9999, true), "🕑2h");
+    }
+
+    #[test]
+    fn test_reset_date_crosses_month_boundary() {
+        let reset = DateTime::parse_from_rfc3339("2025-10-31T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(format_reset_date(reset, Some("utc")), "Oct 31");
+        assert_eq!(format_reset_date(reset, Some("+02:00")), "Nov 1");
+        assert_eq!(format_reset_date(reset, Some("-05:00")), "Oct 31");
+
+        let api = ApiUsageData {
+            five_hour_percent: 0.0,
+            five_hour_resets_at: None,
+            seven_day_percent: 0.0,
+            seven_day_resets_at: Some(reset),
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        };
+        let absolute = format_time_remaining_7d(
+            Some(&api),
+            PlanType::Subscription,
+            ResetDisplay::Absolute,
+            Some("+02:00"),
+        );
+        assert_eq!(absolute.as_deref(), Some("📅Nov 1"));
+
+        let both = format_time_remaining_7d(
+            Some(&api),
+            PlanType::Subscription,
+            ResetDisplay::Both,
+            Some("utc"),
+        )
+        .unwrap();
+        assert!(both.ends_with(" (Oct 31)"), "got {}", both);
+    }
+
+    #[test]
+    fn test_block_info_plan_modes() {
+        let block = Block {
+            cost_usd: 12.5,
+            total_tokens: 1000,
+            ..active_block()
+        };
+        let api = api_usage(37.6, 10.0);
+
+        assert_eq!(
+            format_block_info(&block, PlanMode::PayAsYouGo, Some(&api), false, 0, None).as_deref(),
+            Some("$12.50")
+        );
+        assert_eq!(
+            format_block_info(&block, PlanMode::Flat, Some(&api), false, 0, None).as_deref(),
+            Some("5h:37%")
+        );
+        assert_eq!(
+            format_block_info(&block, PlanMode::Flat, None, false, 0, None).as_deref(),
+            Some("$12.50")
+        );
+    }
+
+    #[test]
+    fn test_format_cost_and_limit() {
+        let mut block = Block::inactive(Utc::now(), 5);
+        block.is_active = true;
+        block.cost_usd = 3.42;
+        let api = api_usage(40.0, 12.0);
+
+        assert_eq!(
+            format_cost_and_limit(&block, Some(&api), 0, None).as_deref(),
+            Some("$3.42 / 40% used")
+        );
+        assert_eq!(format_cost_and_limit(&block, None, 0, None), None);
+
+        block.in_grace = true;
+        assert_eq!(
+            format_cost_and_limit(&block, Some(&api), 0, None).as_deref(),
+            Some("$3.42 / 40% used⏳")
+        );
+
+        block.is_active = false;
+        assert_eq!(format_cost_and_limit(&block, Some(&api), 0, None), None);
+    }
+
+    #[test]
+    fn test_api_usage_5h_reset_time() {
+        let thresholds = Thresholds::default();
+        let mut data = api_usage(40.0, 12.0);
+        let clock_24h = ClockStyle {
+            timezone: Some("utc"),
+            clock_24h: true,
+        };
+        let clock_12h = ClockStyle {
+            timezone: Some("+02:00"),
+            clock_24h: false,
+        };
+
+        // No reset time from the API: just the percentage
+        assert_eq!(
+            plain(format_api_usage_5h(
+                Some(&data),
+                SparkMode::Fraction,
+                false,
+                0,
+                Some(clock_24h),
+                &thresholds
+            ))
+            .as_deref(),
+            Some("5h:40%")
+        );
+
+        data.five_hour_resets_at = Some(
+            "2025-01-01T15:00:00Z"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            plain(format_api_usage_5h(
+                Some(&data),
+                SparkMode::Fraction,
+                false,
+                0,
+                Some(clock_24h),
+                &thresholds
+            ))
+            .as_deref(),
+            Some("5h:40% (→15:00)")
+        );
+        assert_eq!(
+            plain(format_api_usage_5h(
+                Some(&data),
+                SparkMode::Fraction,
+                false,
+                0,
+                Some(clock_12h),
+                &thresholds
+            ))
+            .as_deref(),
+            Some("5h:40% (→5:00pm)")
+        );
+        assert_eq!(
+            plain(format_api_usage_5h(
+                Some(&data),
+                SparkMode::Fraction,
+                false,
+                0,
+                None,
+                &thresholds
+            ))
+            .as_deref(),
+            Some("5h:40%")
+        );
+    }
+
+    #[test]
+    fn test_join_segments_multiline() {
+        let parts = vec![
+            "🤖Opus 4.6".to_string(),
+            "💰$1.00"
+                .red()
+                .to_string(),
+            "🧠10k".to_string(),
+        ];
+        assert_eq!(
+            join_segments(&parts, false)
+                .lines()
+                .count(),
+            1
+        );
+
+        let multiline = join_segments(&parts, true);
+        let lines: Vec<&str> = multiline
+            .lines()
+            .collect();
+        assert_eq!(lines.len(), parts.len());
+        // Each line keeps its own emoji and complete color codes
+        assert_eq!(lines[0], "🤖Opus 4.6");
+        assert_eq!(strip_ansi(lines[1]), "💰$1.00");
+        assert!(lines[1].ends_with("\x1b[39m"));
+        assert_eq!(
+            strip_emojis(&multiline)
+                .lines()
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_truncate_visible() {
+        let dir = replace_home(
+            Path::new("/home/user/projects/some/very/deeply/nested/repository"),
+            Path::new("/home/user"),
+        )
+        .green()
+        .to_string();
+        let line = format!("🤖Opus 4.6 │ {} │ {}", "$1.00".red(), dir);
+
+        let short = truncate_visible(&line, 30);
+        let visible = strip_ansi(&short);
+        assert_eq!(visible, "🤖Opus 4.6 │ $1.00 │ ~/projec…");
+        assert_eq!(UnicodeWidthStr::width(visible.as_str()), 30);
+        // The directory's color is still reset after the cut
+        assert!(short.ends_with("\x1b[39m"));
+
+        // Fits: unchanged
+        assert_eq!(truncate_visible(&line, 200), line);
+        // An emoji is never split: it would straddle the limit, so it's dropped
+        assert_eq!(truncate_visible("ab🤖cd", 4), "ab…");
+        // Each line is cut on its own
+        assert_eq!(truncate_visible("abcdef\nxy", 4), "abc…\nxy");
+    }
+
+    #[test]
+    fn test_format_update() {
+        assert_eq!(format_update("🔼", None, "2.1.0"), "🔼2.1.0");
+        assert_eq!(
+            format_update("🔼", Some("2.0.71"), "2.1.0"),
+            "🔼2.0.71→2.1.0"
+        );
+        assert_eq!(format_update("update:", None, "2.1.0"), "update:2.1.0");
+        assert_eq!(format_update("", Some("2.0.71"), "2.1.0"), "2.0.71→2.1.0");
+    }
+
+    #[test]
+    fn test_hide_zero_segments() {
+        let block = active_block();
+        let burn = BurnRate {
+            cost_per_hour: 0.0,
+            ratio: 0.0,
+            seven_day_ratio: 0.0,
+            critical_limit: LimitType::None,
+            is_at_limit: false,
+            reset_in: None,
+            seven_day_reset_in: None,
+        };
+        let mut api = api_usage(0.4, 0.0);
+        api.seven_day_sonnet_percent = Some(0.0);
+        let t = default_thresholds();
+
+        // Shown by default
+        assert_eq!(
+            format_block_info(&block, PlanMode::PayAsYouGo, None, false, 0, None).as_deref(),
+            Some("$0.00")
+        );
+        assert!(format_burn_rate_component(&burn, PlanType::Api, &burn_style(&t)).is_some());
+        assert_eq!(
+            format_api_usage_sonnet(Some(&api), false, 0, SonnetWhenAbsent::Hide).as_deref(),
+            Some("S7d:0%")
+        );
+
+        // Every zero segment is dropped
+        assert_eq!(
+            format_block_info(&block, PlanMode::PayAsYouGo, None, true, 0, None),
+            None
+        );
+        assert_eq!(
+            format_block_info(&block, PlanMode::Flat, Some(&api), true, 0, None),
+            None
+        );
+        assert_eq!(
+            format_block_info(
+                &Block::inactive(Utc::now(), 5),
+                PlanMode::PayAsYouGo,
+                None,
+                true,
+                0,
+                None
+            ),
+            None
+        );
+        for plan in [PlanType::Api, PlanType::Subscription] {
+            assert_eq!(
+                format_burn_rate_component(
+                    &burn,
+                    plan,
+                    &BurnRateStyle {
+                        hide_zero: true,
+                        ..burn_style(&t)
+                    }
+                ),
+                None
+            );
+        }
+        assert_eq!(
+            format_api_usage_5h(Some(&api), SparkMode::Fraction, true, 0, None, &t),
+            None
+        );
+        assert_eq!(format_api_usage_7d(Some(&api), true, 0, &t), None);
+        assert_eq!(format_session_cost(0.0, true, None), None);
+        assert_eq!(
+            format_session_cost(0.0, false, None).as_deref(),
+            Some("🧾$0.00")
+        );
+        assert_eq!(
+            format_api_usage_sonnet(Some(&api), true, 0, SonnetWhenAbsent::Hide),
+            None
+        );
+        assert_eq!(
+            format_api_usage_compact(
+                Some(&api),
+                &ALL_METRICS,
+                &ApiMetricStyle {
+                    hide_zero: true,
+                    ..api_style(&t)
+                }
+            ),
+            None
+        );
+
+        // Non-zero values are unaffected
+        let block = Block {
+            cost_usd: 0.01,
+            ..block
+        };
+        assert_eq!(
+            format_block_info(&block, PlanMode::PayAsYouGo, None, true, 0, None).as_deref(),
+            Some("$0.01")
+        );
+    }
+
+    #[test]
+    fn test_model_display_name() {
+        assert_eq!(model_display_name("claude-opus-4-6"), "Opus 4.6");
+        assert_eq!(model_display_name("claude-opus-4-1-20250805"), "Opus 4.1");
+        assert_eq!(model_display_name("claude-sonnet-4-20250514"), "Sonnet 4");
+        assert_eq!(model_display_name("some-other-model"), "some-other-model");
+    }
+
+    #[test]
+    fn test_format_project_prefix() {
+        let workspace = |dir: &str| Workspace {
+            current_dir: dir.to_string(),
+        };
+        assert_eq!(
+            format_project_prefix(Some(&workspace("/home/me/src/my-crate"))).as_deref(),
+            Some("[my-crate] ")
+        );
+        assert_eq!(
+            format_project_prefix(Some(&workspace("/home/me/src/my-crate/"))).as_deref(),
+            Some("[my-crate] ")
+        );
+        assert_eq!(format_project_prefix(Some(&workspace("/"))), None);
+        assert_eq!(format_project_prefix(None), None);
+    }
+
+    #[test]
+    fn test_format_model_name_modes() {
+        let model = |id: Option<&str>| ModelInfo {
+            id: id.map(str::to_string),
+            display_name: "Opus 4.6 (1M context)".to_string(),
+        };
+        let opus = model(Some("claude-opus-4-6[1m]"));
+
+        assert_eq!(
+            format_model_name(&opus, ModelDisplay::Full, "Claude"),
+            "Opus 4.6 (1M)"
+        );
+        assert_eq!(
+            format_model_name(&opus, ModelDisplay::Short, "Claude"),
+            "O4.6"
+        );
+        assert_eq!(
+            format_model_name(&opus, ModelDisplay::Id, "Claude"),
+            "claude-opus-4-6[1m]"
+        );
+
+        for (id, short) in [
+            ("claude-sonnet-4-5-20250929", "S4.5"),
+            ("claude-opus-4-1-20250805", "O4.1"),
+            ("claude-sonnet-4-20250514", "S4"),
+            ("claude-haiku-4-5-20251001", "H4.5"),
+        ] {
+            assert_eq!(
+                format_model_name(&model(Some(id)), ModelDisplay::Short, "Claude"),
+                short
+            );
+        }
+
+        // Unknown or missing ids fall back to the display name
+        assert_eq!(
+            format_model_name(
+                &model(Some("some-other-model")),
+                ModelDisplay::Short,
+                "Claude"
+            ),
+            "Opus 4.6 (1M)"
+        );
+        assert_eq!(
+            format_model_name(&model(None), ModelDisplay::Id, "Claude"),
+            "Opus 4.6 (1M)"
+        );
+    }
+
+    #[test]
+    fn test_format_model_name_blank_display_name() {
+        let model = |id: Option<&str>, display_name: &str| ModelInfo {
+            id: id.map(str::to_string),
+            display_name: display_name.to_string(),
+        };
 
-            format!("{}k({}%)", info.tokens / 1000, color)
+        for blank in ["", "   "] {
+            assert_eq!(
+                format_model_name(&model(None, blank), ModelDisplay::Full, "Claude"),
+                "Claude"
+            );
+            assert_eq!(
+                format_model_name(
+                    &model(Some("some-other-model"), blank),
+                    ModelDisplay::Short,
+                    "?"
+                ),
+                "?"
+            );
         }
-        None => "N/A".to_string(),
+        assert_eq!(
+            format_model_name(&model(None, "  Sonnet 4.5\n"), ModelDisplay::Full, "Claude"),
+            "Sonnet 4.5"
+        );
+        // Known ids don't need the display name
+        assert_eq!(
+            format_model_name(
+                &model(Some("claude-sonnet-4-5-20250929"), ""),
+                ModelDisplay::Short,
+                "Claude"
+            ),
+            "S4.5"
+        );
     }
-}
-
-/// Format currency with locale-based formatting
-pub fn format_currency(amount: f64) -> String {
-    format!("${:.2}", amount)
-}
 
-/// Map decimal portion (0.0-0.9) to Unicode block character (vertical fill)
-fn decimal_to_block(value: f64) -> char {
-    const BLOCKS: [char; 10] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█', '█'];
-    let idx = ((value.fract() * 10.0) as usize).min(9);
-    BLOCKS[idx]
-}
+    fn api_usage(five_hour_percent: f64, seven_day_percent: f64) -> ApiUsageData {
+        ApiUsageData {
+            five_hour_percent,
+            five_hour_resets_at: None,
+            seven_day_percent,
+            seven_day_resets_at: None,
+            seven_day_sonnet_percent: None,
+            seven_day_opus_percent: None,
+            accounts: Vec::new(),
+        }
+    }
 
-/// Format 5h API usage
-pub fn format_api_usage_5h(api_usage: Option<&ApiUsageData>) -> Option<String> {
-    api_usage.map(|api| {
-        let five_hour_int = api.five_hour_percent as u32;
-        let five_hour_block = decimal_to_block(api.five_hour_percent);
-        if five_hour_block == ' ' {
-            format!("5h:{}%", five_hour_int)
-        } else {
-            format!("5h:{}%{}", five_hour_int, five_hour_block)
+    /// Active block that started now, with one session
+    fn active_block() -> Block {
+        Block {
+            is_active: true,
+            hours_remaining: Some(5.0),
+            session_count: 1,
+            ..Block::inactive(Utc::now(), 5)
         }
-    })
-}
+    }
 
-/// Format 7d API usage
-pub fn format_api_usage_7d(api_usage: Option<&ApiUsageData>) -> Option<String> {
-    api_usage.map(|api| format!("7d:{}%", api.seven_day_percent as u32))
-}
+    /// Metric style with every option off
+    fn api_style(thresholds: &Thresholds) -> ApiMetricStyle<'_> {
+        ApiMetricStyle {
+            spark_mode: SparkMode::Fraction,
+            hide_zero: false,
+            pct_decimals: 0,
+            reset_clock: None,
+            per_account: false,
+            sonnet_absent: SonnetWhenAbsent::Hide,
+            thresholds,
+        }
+    }
 
-/// Format Sonnet 7d API usage
-pub fn format_api_usage_sonnet(api_usage: Option<&ApiUsageData>) -> Option<String> {
-    api_usage.map(|api| format!("S7d:{}%", api.seven_day_sonnet_percent as u32))
-}
+    /// `text` without its colors
+    fn plain(text: Option<String>) -> Option<String> {
+        text.map(|text| strip_ansi(&text))
+    }
 
-pub fn strip_emojis(s: &str) -> String {
-    s.chars()
-        .filter(|c| {
-            let cp = *c as u32;
-            !(0x1F300..=0x1FAFF).contains(&cp) && cp != 0x200B
-        })
-        .collect()
-}
+    #[test]
+    fn test_format_api_segment_exhausted() {
+        let below = api_usage(99.0, 40.0);
+        assert_eq!(
+            format_api_segment("📊5h:99%".to_string(), Some(&below)),
+            "📊5h:99%"
+        );
 
-/// Format directory path with home replacement and color
-pub fn format_directory(path: &str) -> String {
-    let home = crate::paths::home_dir()
-        .ok()
-        .and_then(|p| {
-            p.to_str()
-                .map(String::from)
-        });
-
-    let formatted = match home {
-        Some(h) if path.starts_with(&h) => path.replacen(&h, "~", 1),
-        _ => path.to_string(),
-    };
+        let at_limit = api_usage(100.0, 40.0);
+        let result = format_api_segment("📊5h:100%".to_string(), Some(&at_limit));
+        assert!(
+            result.starts_with("🛑"),
+            "expected 🛑 prefix in '{}'",
+            result
+        );
+        assert_eq!(strip_ansi_codes(&result), "🛑📊5h:100%");
 
-    formatted
-        .green()
-        .to_string()
-}
+        let over_limit = api_usage(20.0, 120.0);
+        let result = format_api_segment("📊5h:20% 7d:120%".to_string(), Some(&over_limit));
+        assert_eq!(strip_ansi_codes(&result), "🛑📊5h:20% 7d:120%");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(format_api_segment("x".to_string(), None), "x");
+    }
 
     #[test]
-    fn test_decimal_to_block_zero() {
-        assert_eq!(decimal_to_block(0.0), ' ');
-        assert_eq!(decimal_to_block(50.0), ' ');
+    fn test_format_block_window() {
+        let mut block = Block::inactive(
+            "2025-01-01T14:00:00Z"
+                .parse()
+                .unwrap(),
+            5,
+        );
+        assert_eq!(format_block_window(&block, Some("utc"), true), None);
+
+        block.is_active = true;
+        assert_eq!(
+            format_block_window(&block, Some("utc"), true).as_deref(),
+            Some("🕐14:00–19:00")
+        );
+        assert_eq!(
+            format_block_window(&block, Some("UTC"), false).as_deref(),
+            Some("🕐2:00pm–7:00pm")
+        );
+        assert_eq!(
+            format_block_window(&block, Some("+02:00"), true).as_deref(),
+            Some("🕐16:00–21:00")
+        );
     }
 
     #[test]
-    fn test_decimal_to_block_fractions() {
-        assert_eq!(decimal_to_block(0.1), '▁');
-        assert_eq!(decimal_to_block(0.5), '▅');
-        assert_eq!(decimal_to_block(0.9), '█');
+    fn test_format_context_remaining() {
+        let thresholds = Thresholds::default();
+        let info = ContextInfo {
+            tokens: 123_400,
+            percentage: 79,
+            remaining: Some(31_600),
+        };
+        let render = |compact, show_remaining| {
+            strip_ansi(&format_context(
+                Some(&info),
+                &thresholds,
+                compact,
+                show_remaining,
+            ))
+        };
+        assert_eq!(render(false, false), "123k(79%)");
+        assert_eq!(render(false, true), "123k(79%, 31k left)");
+        assert_eq!(render(true, true), "123.4k(79%, 31.6k left)");
+
+        let over = ContextInfo {
+            tokens: 170_000,
+            percentage: 100,
+            remaining: Some(0),
+        };
+        assert_eq!(
+            strip_ansi(&format_context(Some(&over), &thresholds, false, true)),
+            "170k(100%, 0k left)"
+        );
+
+        // Not looked up: nothing to show
+        let unknown = ContextInfo {
+            remaining: None,
+            ..over
+        };
+        assert_eq!(
+            strip_ansi(&format_context(Some(&unknown), &thresholds, false, true)),
+            "170k(100%)"
+        );
     }
 
     #[test]
-    fn test_format_api_usage_5h_no_trailing_space() {
-        let data = ApiUsageData {
-            five_hour_percent: 37.0,
-            five_hour_resets_at: None,
-            seven_day_percent: 10.0,
-            seven_day_resets_at: None,
-            seven_day_sonnet_percent: 0.0,
-        };
-        let result = format_api_usage_5h(Some(&data)).unwrap();
-        assert_eq!(result, "5h:37%");
-        assert!(!result.ends_with(' '));
+    fn test_format_tokens_compact() {
+        assert_eq!(format_tokens_compact(999), "999");
+        assert_eq!(format_tokens_compact(95_510), "95.5k");
+        assert_eq!(format_tokens_compact(1_250_000), "1.2M");
+        assert_eq!(format_tokens_compact(999_960), "1.0M");
     }
 
     #[test]
-    fn test_format_api_usage_5h_with_block() {
-        let data = ApiUsageData {
-            five_hour_percent: 37.5,
-            five_hour_resets_at: None,
-            seven_day_percent: 10.0,
-            seven_day_resets_at: None,
-            seven_day_sonnet_percent: 0.0,
-        };
-        let result = format_api_usage_5h(Some(&data)).unwrap();
-        assert_eq!(result, "5h:37%▅");
+    fn test_format_number_locales() {
+        let en = NumberFormat::from_locale("en_US.UTF-8").unwrap();
+        assert_eq!(format_number(1_234_567.891, 2, &en), "1,234,567.89");
+
+        let de = NumberFormat::from_locale("de").unwrap();
+        assert_eq!(format_number(1_234_567.891, 2, &de), "1.234.567,89");
+        assert_eq!(format_number(999.5, 0, &de), "1.000");
+
+        let fr = NumberFormat::from_locale("fr-FR").unwrap();
+        assert_eq!(
+            format_number(1_234_567.891, 2, &fr),
+            "1\u{202F}234\u{202F}567,89"
+        );
+        assert_eq!(format_number(12.0, 2, &fr), "12,00");
+
+        assert_eq!(format_number(-1234.5, 1, &en), "-1,234.5");
+        assert_eq!(NumberFormat::from_locale("xx"), None);
     }
 
     #[test]
     fn test_format_currency() {
-        assert_eq!(format_currency(12.345), "$12.35");
-        assert_eq!(format_currency(0.0), "$0.00");
+        assert_eq!(format_currency(12.345, None), "$12.35");
+        assert_eq!(format_currency(0.0, None), "$0.00");
+    }
+
+    #[test]
+    fn test_format_currency_with_locale() {
+        let de = NumberFormat::from_locale("de");
+        assert_eq!(format_currency(1234.5, de), "$1.234,50");
+        assert_eq!(
+            format_cost_delta(Some(0.0), 1234.5, de).as_deref(),
+            Some(" (+$1.234,50)")
+        );
+        assert_eq!(format_currency(1234.5, None), "$1234.50");
     }
 
     #[test]
@@ -443,10 +2533,10 @@ mod tests {
         };
         let t = default_thresholds();
         let rate_api =
-            format_burn_rate_component(&safe_burn, PlanType::Api, true, false, &t).unwrap();
+            format_burn_rate_component(&safe_burn, PlanType::Api, &burn_style(&t)).unwrap();
         assert!(rate_api.contains("$1.50/h"));
         let rate_sub =
-            format_burn_rate_component(&safe_burn, PlanType::Subscription, true, false, &t)
+            format_burn_rate_component(&safe_burn, PlanType::Subscription, &burn_style(&t))
                 .unwrap();
         assert!(rate_sub.contains("50%"));
 
@@ -460,7 +2550,7 @@ mod tests {
             seven_day_reset_in: None,
         };
         let warn =
-            format_burn_rate_component(&warning_burn, PlanType::Api, true, false, &t).unwrap();
+            format_burn_rate_component(&warning_burn, PlanType::Api, &burn_style(&t)).unwrap();
         assert!(warn.contains("$10.00/h"));
         assert!(warn.contains("5h"));
 
@@ -474,7 +2564,7 @@ mod tests {
             seven_day_reset_in: None,
         };
         let danger =
-            format_burn_rate_component(&danger_burn, PlanType::Subscription, true, false, &t)
+            format_burn_rate_component(&danger_burn, PlanType::Subscription, &burn_style(&t))
                 .unwrap();
         assert!(danger.contains("140%"));
         assert!(danger.contains("5h"));
@@ -493,7 +2583,7 @@ mod tests {
         };
         let t = default_thresholds();
         let result =
-            format_burn_rate_component(&burn_with_7d, PlanType::Subscription, true, false, &t)
+            format_burn_rate_component(&burn_with_7d, PlanType::Subscription, &burn_style(&t))
                 .unwrap();
         assert!(result.contains("50%"));
         assert!(result.contains("5h"));
@@ -510,7 +2600,7 @@ mod tests {
             seven_day_reset_in: None,
         };
         let result =
-            format_burn_rate_component(&burn_7d_critical, PlanType::Subscription, true, false, &t)
+            format_burn_rate_component(&burn_7d_critical, PlanType::Subscription, &burn_style(&t))
                 .unwrap();
         assert!(result.contains("110%"));
         assert!(result.contains(" 7d"));
@@ -535,7 +2625,7 @@ mod tests {
         };
         let t = default_thresholds();
         let result =
-            format_burn_rate_component(&burn, PlanType::Subscription, true, false, &t).unwrap();
+            format_burn_rate_component(&burn, PlanType::Subscription, &burn_style(&t)).unwrap();
         assert_eq!(
             result
                 .matches('%')
@@ -696,6 +2786,139 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_burn_rate_custom_emoji() {
+        let t = Thresholds {
+            burn_emoji_ok: "●".to_string(),
+            burn_emoji_warn: "◐".to_string(),
+            burn_emoji_critical: "◉".to_string(),
+            ..Thresholds::default()
+        };
+        let mut burn = BurnRate {
+            cost_per_hour: 5.0,
+            ratio: 0.5,
+            seven_day_ratio: 0.0,
+            critical_limit: LimitType::FiveHour,
+            is_at_limit: false,
+            reset_in: None,
+            seven_day_reset_in: None,
+        };
+        let result =
+            format_burn_rate_component(&burn, PlanType::Subscription, &burn_style(&t)).unwrap();
+        assert!(result.starts_with("●"), "expected ● in '{}'", result);
+
+        burn.ratio = 0.9;
+        let result =
+            format_burn_rate_component(&burn, PlanType::Subscription, &burn_style(&t)).unwrap();
+        assert!(result.starts_with("◐"), "expected ◐ in '{}'", result);
+
+        burn.ratio = 1.2;
+        let result =
+            format_burn_rate_component(&burn, PlanType::Subscription, &burn_style(&t)).unwrap();
+        assert!(result.starts_with("◉"), "expected ◉ in '{}'", result);
+        assert!(
+            result.contains(
+                "120%"
+                    .red()
+                    .to_string()
+                    .as_str()
+            ),
+            "color should still apply in '{}'",
+            result
+        );
+
+        burn.is_at_limit = true;
+        let result =
+            format_burn_rate_component(&burn, PlanType::Subscription, &burn_style(&t)).unwrap();
+        assert_eq!(result, "◉limit");
+    }
+
+    #[test]
+    fn test_format_burn_rate_per_block() {
+        let burn = BurnRate {
+            cost_per_hour: 1.5,
+            ratio: 0.5,
+            seven_day_ratio: 0.0,
+            critical_limit: LimitType::FiveHour,
+            is_at_limit: false,
+            reset_in: None,
+            seven_day_reset_in: None,
+        };
+        let t = default_thresholds();
+        let rate = |per_block_hours| {
+            format_burn_rate_component(
+                &burn,
+                PlanType::Api,
+                &BurnRateStyle {
+                    per_block_hours,
+                    ..burn_style(&t)
+                },
+            )
+            .unwrap()
+        };
+
+        assert!(rate(None).contains("$1.50/h"), "got {}", rate(None));
+        assert!(rate(Some(5)).contains("$7.50/5h"), "got {}", rate(Some(5)));
+        assert!(rate(Some(8)).contains("$12.00/8h"), "got {}", rate(Some(8)));
+
+        // A rate too small to show per hour can still show per block
+        let slow = BurnRate {
+            cost_per_hour: 0.002,
+            ..burn
+        };
+        let slow_rate = |per_block_hours| {
+            format_burn_rate_component(
+                &slow,
+                PlanType::Api,
+                &BurnRateStyle {
+                    hide_zero: true,
+                    per_block_hours,
+                    ..burn_style(&t)
+                },
+            )
+        };
+        assert_eq!(slow_rate(None), None);
+        assert!(slow_rate(Some(5)).is_some_and(|s| s.contains("$0.01/5h")));
+    }
+
+    #[test]
+    fn test_format_burn_rate_custom_unit() {
+        let t = Thresholds {
+            burn_rate_unit: " per hour".to_string(),
+            ..Thresholds::default()
+        };
+        let burn = BurnRate {
+            cost_per_hour: 1.5,
+            ratio: 0.5,
+            seven_day_ratio: 0.0,
+            critical_limit: LimitType::FiveHour,
+            is_at_limit: false,
+            reset_in: None,
+            seven_day_reset_in: None,
+        };
+        let result = format_burn_rate_component(&burn, PlanType::Api, &burn_style(&t)).unwrap();
+        assert!(
+            result.starts_with("🔥"),
+            "emoji stays first in '{}'",
+            result
+        );
+        assert!(
+            result.contains(
+                "$1.50 per hour"
+                    .green()
+                    .to_string()
+                    .as_str()
+            ),
+            "unit should be colored with the cost in '{}'",
+            result
+        );
+
+        // Subscription plans show a percentage, no unit
+        let result =
+            format_burn_rate_component(&burn, PlanType::Subscription, &burn_style(&t)).unwrap();
+        assert!(!result.contains("per hour"));
+    }
+
     // --- ETA-only mode tests ---
 
     #[test]
@@ -778,9 +3001,11 @@ mod tests {
         let result = format_burn_rate_component(
             &burn,
             PlanType::Subscription,
-            false,
-            true,
-            &default_thresholds(),
+            &BurnRateStyle {
+                show_rate: false,
+                show_eta: true,
+                ..burn_style(&default_thresholds())
+            },
         );
         assert!(
             result.is_none(),
@@ -828,9 +3053,10 @@ mod tests {
             format_burn_rate_component(
                 &burn,
                 PlanType::Subscription,
-                false,
-                false,
-                &default_thresholds()
+                &BurnRateStyle {
+                    show_rate: false,
+                    ..burn_style(&default_thresholds())
+                }
             )
             .is_none()
         );
@@ -858,6 +3084,24 @@ mod tests {
         assert_eq!(format_eta(Duration::hours(23)), "23h");
     }
 
+    #[test]
+    fn test_format_token_expiry() {
+        let now = Utc::now();
+        assert_eq!(format_token_expiry(None, 24), None);
+        assert_eq!(
+            format_token_expiry(Some(now + Duration::hours(48)), 24),
+            None
+        );
+        assert_eq!(
+            format_token_expiry(Some(now + Duration::hours(2)), 24).as_deref(),
+            Some("🔑token expiring")
+        );
+        assert_eq!(
+            format_token_expiry(Some(now - Duration::hours(1)), 24).as_deref(),
+            Some("🔑token expired")
+        );
+    }
+
     #[test]
     fn test_strip_emojis() {
         assert_eq!(strip_emojis("🤖Claude"), "Claude");
@@ -866,10 +3110,114 @@ mod tests {
         assert_eq!(strip_emojis("no emojis here"), "no emojis here");
     }
 
+    #[test]
+    fn test_format_data_source() {
+        let home = Path::new("/home/u");
+        let mut block = active_block();
+        assert_eq!(format_data_source(&block, Some(home)), None);
+
+        block.latest_source = Some(std::path::PathBuf::from(
+            "/home/u/.claude/projects/-home-u-src-my-repo/abc.jsonl",
+        ));
+        assert_eq!(
+            format_data_source(&block, Some(home)).as_deref(),
+            Some("[proj:src-my-repo]")
+        );
+        assert_eq!(
+            format_data_source(&block, None).as_deref(),
+            Some("[proj:-home-u-src-my-repo]")
+        );
+
+        // Outside home, or home itself, keeps the full encoded name
+        assert_eq!(short_project_name("-srv-work", Some(home)), "-srv-work");
+        assert_eq!(short_project_name("-home-u", Some(home)), "-home-u");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        let colored = format!("🔥{} │ {}", "$4.20/h".red(), "5h:37%".green());
+        assert_eq!(strip_ansi(&colored), "🔥$4.20/h │ 5h:37%");
+        assert_eq!(strip_ansi("plain │ text"), "plain │ text");
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("cyan"), Some(AnsiColors::Cyan));
+        assert_eq!(parse_color("Bright-Blue"), Some(AnsiColors::BrightBlue));
+        assert_eq!(parse_color(" grey "), Some(AnsiColors::BrightBlack));
+        assert_eq!(parse_color("chartreuse"), None);
+        assert_eq!(parse_color(""), None);
+    }
+
+    #[test]
+    fn test_format_directory_color() {
+        let result = format_directory(Path::new("/srv/project"), AnsiColors::Cyan);
+        assert_eq!(
+            result,
+            "/srv/project"
+                .cyan()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_replace_home() {
+        let home = Path::new("/home/user");
+        assert_eq!(
+            replace_home(Path::new("/home/user/project"), home),
+            "~/project"
+        );
+        assert_eq!(replace_home(Path::new("/home/user"), home), "~");
+        assert_eq!(
+            replace_home(Path::new("/home/user2/x"), home),
+            "/home/user2/x"
+        );
+        assert_eq!(
+            replace_home(Path::new("/srv/project"), home),
+            "/srv/project"
+        );
+    }
+
+    #[test]
+    fn test_replace_home_replacement_char() {
+        let home = Path::new("/home/user");
+        assert_eq!(
+            replace_home(Path::new("/home/user/caf\u{FFFD}"), home),
+            "~/caf\u{FFFD}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replace_home_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let home = Path::new("/home/user");
+        let path = Path::new(OsStr::from_bytes(b"/home/user/caf\xe9"));
+        assert_eq!(replace_home(path, home), "~/caf\u{FFFD}");
+
+        let home = Path::new(OsStr::from_bytes(b"/home/\xe9"));
+        let path = Path::new(OsStr::from_bytes(b"/home/\xe9/project"));
+        assert_eq!(replace_home(path, home), "~/project");
+    }
+
     fn default_thresholds() -> Thresholds {
         Thresholds::default()
     }
 
+    /// Burn rate style showing only the rate
+    fn burn_style(thresholds: &Thresholds) -> BurnRateStyle<'_> {
+        BurnRateStyle {
+            show_rate: true,
+            show_eta: false,
+            hide_zero: false,
+            per_block_hours: None,
+            number_format: None,
+            thresholds,
+        }
+    }
+
     fn verbose(
         burn_rate: &BurnRate,
         plan_type: PlanType,
@@ -879,9 +3227,11 @@ mod tests {
         let result = format_burn_rate_component(
             burn_rate,
             plan_type,
-            show_rate,
-            show_eta,
-            &default_thresholds(),
+            &BurnRateStyle {
+                show_rate,
+                show_eta,
+                ..burn_style(&default_thresholds())
+            },
         )
         .unwrap_or_default();
         eprintln!("  {}", result);
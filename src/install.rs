@@ -1,5 +1,5 @@
-use crate::paths::claude_config_dir;
 use anyhow::{Context, Result};
+use ccusage_statusline_rs::paths::claude_config_dir;
 use path_slash::PathExt;
 use serde_json::{Value, json};
 use std::fs;
@@ -10,6 +10,30 @@ fn get_settings_path() -> Result<PathBuf> {
     Ok(claude_config_dir()?.join("settings.json"))
 }
 
+/// Parse settings.json, which must hold a JSON object at its root
+fn parse_settings(content: &str) -> Result<Value> {
+    let settings: Value =
+        serde_json::from_str(content).context("Failed to parse settings.json (invalid JSON)")?;
+    if !settings.is_object() {
+        anyhow::bail!(
+            "settings.json must contain a JSON object at its root (e.g. {{}}), found {}",
+            json_kind(&settings)
+        );
+    }
+    Ok(settings)
+}
+
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
 /// Prompt user for yes/no confirmation
 fn prompt_yes_no(prompt: &str) -> Result<bool> {
     print!("{} [y/n]: ", prompt);
@@ -23,8 +47,63 @@ fn prompt_yes_no(prompt: &str) -> Result<bool> {
         .eq_ignore_ascii_case("y"))
 }
 
+/// Print the old and new `statusLine` values without writing anything
+fn print_dry_run(old: Option<&Value>, new: Option<&Value>) -> Result<()> {
+    println!("Dry run — settings.json will not be modified.");
+    println!("Current statusLine:");
+    match old {
+        Some(v) => println!("{}", serde_json::to_string_pretty(v)?),
+        None => println!("(none)"),
+    }
+    println!("New statusLine:");
+    match new {
+        Some(v) => println!("{}", serde_json::to_string_pretty(v)?),
+        None => println!("(none)"),
+    }
+    Ok(())
+}
+
+/// What an existing `statusLine` setting holds, relative to our binary
+#[derive(Debug, PartialEq)]
+enum ExistingStatusLine {
+    None,
+    /// A command that already runs this binary (possibly with extra flags)
+    Ours,
+    OtherCommand,
+    /// Not a `type: "command"` entry (e.g. a plugin or hand-written object)
+    NonCommand,
+}
+
+fn classify_status_line(existing: Option<&Value>, binary_path: &str) -> ExistingStatusLine {
+    let Some(existing) = existing else {
+        return ExistingStatusLine::None;
+    };
+    let command = existing
+        .get("command")
+        .and_then(Value::as_str);
+    match (
+        existing
+            .get("type")
+            .and_then(Value::as_str),
+        command,
+    ) {
+        (Some("command"), Some(command)) => {
+            if command
+                .split_whitespace()
+                .next()
+                == Some(binary_path)
+            {
+                ExistingStatusLine::Ours
+            } else {
+                ExistingStatusLine::OtherCommand
+            }
+        }
+        _ => ExistingStatusLine::NonCommand,
+    }
+}
+
 /// Install statusLine configuration
-pub fn install() -> Result<()> {
+pub fn install(dry_run: bool) -> Result<()> {
     let config_dir = claude_config_dir()?;
     if !config_dir.exists() {
         anyhow::bail!(
@@ -37,24 +116,14 @@ pub fn install() -> Result<()> {
 
     let mut settings: Value = if settings_path.exists() {
         let content = fs::read_to_string(&settings_path).context("Failed to read settings file")?;
-        serde_json::from_str(&content).context("Failed to parse settings.json (invalid JSON)")?
+        parse_settings(&content)?
     } else {
-        println!("Creating new settings file: {}", settings_path.display());
+        if !dry_run {
+            println!("Creating new settings file: {}", settings_path.display());
+        }
         json!({})
     };
 
-    // Check if statusLine already exists
-    if let Some(existing) = settings.get("statusLine") {
-        println!("⚠️  statusLine is already configured:");
-        println!("{}", serde_json::to_string_pretty(existing)?);
-        println!();
-
-        if !prompt_yes_no("Do you want to overwrite it?")? {
-            println!("Installation cancelled.");
-            return Ok(());
-        }
-    }
-
     // Get the current binary path. Claude Code invokes statusLine via Git Bash on
     // Windows: backslashes are escape chars and \\?\/UNC prefixes are unrunnable.
     // dunce::simplified strips verbatim prefixes when safe; path-slash converts
@@ -72,12 +141,43 @@ pub fn install() -> Result<()> {
         );
     }
 
+    // Check if statusLine already exists; a dry run reports it the same way
+    // but does not ask
+    if let Some(existing) = settings.get("statusLine") {
+        match classify_status_line(Some(existing), &binary_path_str) {
+            ExistingStatusLine::Ours => {
+                println!("✅ statusLine already runs this binary. Nothing to do.");
+                return Ok(());
+            }
+            ExistingStatusLine::NonCommand => {
+                println!("⚠️  statusLine is configured but is not a command:");
+                println!("{}", serde_json::to_string_pretty(existing)?);
+                println!();
+                println!("Overwriting it will discard this configuration entirely.");
+            }
+            ExistingStatusLine::OtherCommand | ExistingStatusLine::None => {
+                println!("⚠️  statusLine is already configured:");
+                println!("{}", serde_json::to_string_pretty(existing)?);
+                println!();
+            }
+        }
+
+        if !dry_run && !prompt_yes_no("Do you want to overwrite it?")? {
+            println!("Installation cancelled.");
+            return Ok(());
+        }
+    }
+
     // Create statusLine configuration
     let status_line_config = json!({
         "type": "command",
         "command": binary_path_str
     });
 
+    if dry_run {
+        return print_dry_run(settings.get("statusLine"), Some(&status_line_config));
+    }
+
     settings["statusLine"] = status_line_config;
 
     // Write back to file
@@ -93,7 +193,7 @@ pub fn install() -> Result<()> {
 }
 
 /// Uninstall statusLine configuration
-pub fn uninstall() -> Result<()> {
+pub fn uninstall(dry_run: bool) -> Result<()> {
     let settings_path = get_settings_path()?;
 
     if !settings_path.exists() {
@@ -104,8 +204,7 @@ pub fn uninstall() -> Result<()> {
     // Read and parse settings
     let content = fs::read_to_string(&settings_path).context("Failed to read settings file")?;
 
-    let mut settings: Value =
-        serde_json::from_str(&content).context("Failed to parse settings.json (invalid JSON)")?;
+    let mut settings = parse_settings(&content)?;
 
     // Check if statusLine exists
     if settings
@@ -116,6 +215,10 @@ pub fn uninstall() -> Result<()> {
         return Ok(());
     }
 
+    if dry_run {
+        return print_dry_run(settings.get("statusLine"), None);
+    }
+
     // Remove statusLine
     if let Some(obj) = settings.as_object_mut() {
         obj.remove("statusLine");
@@ -131,3 +234,81 @@ pub fn uninstall() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINARY: &str = "/home/u/.local/bin/ccusage-statusline-rs";
+
+    #[test]
+    fn test_parse_settings_requires_object() {
+        assert!(
+            parse_settings(r#"{"model": "opus"}"#)
+                .unwrap()
+                .is_object()
+        );
+
+        for (content, kind) in [("[1, 2]", "an array"), (r#""statusLine""#, "a string")] {
+            let err = parse_settings(content)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("must contain a JSON object"), "got {}", err);
+            assert!(err.contains(kind), "got {}", err);
+        }
+
+        let err = parse_settings("{not json")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid JSON"), "got {}", err);
+    }
+
+    #[test]
+    fn test_classify_missing_status_line() {
+        let settings = json!({});
+        assert_eq!(
+            classify_status_line(settings.get("statusLine"), BINARY),
+            ExistingStatusLine::None
+        );
+    }
+
+    #[test]
+    fn test_classify_our_command() {
+        let existing = json!({ "type": "command", "command": BINARY });
+        assert_eq!(
+            classify_status_line(Some(&existing), BINARY),
+            ExistingStatusLine::Ours
+        );
+
+        let with_flags =
+            json!({ "type": "command", "command": format!("{} --profile work", BINARY) });
+        assert_eq!(
+            classify_status_line(Some(&with_flags), BINARY),
+            ExistingStatusLine::Ours
+        );
+    }
+
+    #[test]
+    fn test_classify_other_command() {
+        let existing = json!({ "type": "command", "command": "~/.claude/statusline.sh" });
+        assert_eq!(
+            classify_status_line(Some(&existing), BINARY),
+            ExistingStatusLine::OtherCommand
+        );
+    }
+
+    #[test]
+    fn test_classify_non_command() {
+        let plugin = json!({ "type": "plugin", "name": "fancy-line", "padding": 2 });
+        assert_eq!(
+            classify_status_line(Some(&plugin), BINARY),
+            ExistingStatusLine::NonCommand
+        );
+
+        let no_command = json!({ "type": "command" });
+        assert_eq!(
+            classify_status_line(Some(&no_command), BINARY),
+            ExistingStatusLine::NonCommand
+        );
+    }
+}
@@ -0,0 +1,541 @@
+//! Claude Code usage statusline.
+//!
+//! The binary wraps [`generate_statusline`]; other tools can depend on this
+//! crate to render the same statusline from their own [`HookData`].
+
+mod alert;
+pub mod api_usage;
+pub mod blocks;
+pub mod burn_rate;
+pub mod cache;
+pub mod ccusage_export;
+mod claude_binary;
+mod claude_update;
+pub mod config;
+pub mod context;
+pub mod csv_export;
+pub mod format;
+pub mod lifetime;
+pub mod net;
+pub mod paths;
+pub mod pricing;
+pub mod types;
+
+use anyhow::Result;
+use cache::{create_cache_dir, get_cache_dir};
+use config::{PlanMode, StatusElement, UsageSource};
+use format::*;
+use owo_colors::{AnsiColors, OwoColorize};
+use pricing::PricingFetcher;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use types::{Block, RenderHistory, RenderedStatusline, Transcript, UsageData};
+
+pub use alert::wait_for_notifications;
+pub use blocks::find_active_block;
+pub use burn_rate::calculate_burn_rate;
+pub use config::StatuslineConfig;
+pub use context::calculate_context;
+pub use paths::{find_claude_paths, find_claude_paths_with};
+pub use types::HookData;
+
+/// Find the active block from the configured usage source, falling back to
+/// scanning JSONL transcripts if a ccusage export cannot be read
+pub fn load_active_block(
+    claude_paths: &[PathBuf],
+    pricing: &PricingFetcher,
+    statusline_config: &StatuslineConfig,
+) -> Result<Block> {
+    load_active_block_with(claude_paths, pricing, statusline_config, None)
+}
+
+/// Like `load_active_block`, reusing the already parsed `transcript` in the
+/// JSONL scan
+pub fn load_active_block_with(
+    claude_paths: &[PathBuf],
+    pricing: &PricingFetcher,
+    statusline_config: &StatuslineConfig,
+    transcript: Option<&Transcript>,
+) -> Result<Block> {
+    if let UsageSource::CcusageExport(path) = &statusline_config.usage_source {
+        match ccusage_export::find_active_block(
+            path,
+            statusline_config.block_duration_hours,
+            chrono::Utc::now(),
+        ) {
+            Ok(block) => return Ok(block),
+            Err(e) => {
+                if std::io::stderr().is_terminal() {
+                    eprintln!("Falling back to JSONL usage: {:#}", e);
+                }
+            }
+        }
+    }
+
+    blocks::find_active_block_with(
+        claude_paths,
+        pricing,
+        &statusline_config.scan_options(),
+        transcript,
+    )
+}
+
+/// Prints per-stage durations to stderr; does nothing when disabled
+pub struct StageTimer {
+    last: Option<Instant>,
+}
+
+impl StageTimer {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            last: enabled.then(Instant::now),
+        }
+    }
+
+    /// Start the next stage now, without reporting the time since the last
+    /// one (e.g. when a nested timer reported it)
+    pub fn restart(&mut self) {
+        if self
+            .last
+            .is_some()
+        {
+            self.last = Some(Instant::now());
+        }
+    }
+
+    /// Report the time since the previous stage as `name`
+    pub fn stage(&mut self, name: &str) {
+        if let Some(last) = self.last {
+            let now = Instant::now();
+            eprintln!(
+                "{:>14}: {:>7.2}ms",
+                name,
+                (now - last).as_secs_f64() * 1000.0
+            );
+            self.last = Some(now);
+        }
+    }
+}
+
+/// Generate statusline output
+pub fn generate_statusline(
+    hook_data: &HookData,
+    statusline_config: &config::StatuslineConfig,
+) -> Result<String> {
+    generate_statusline_with_timings(hook_data, statusline_config, false)
+}
+
+/// Generate statusline output, printing how long each stage took to stderr
+/// when `timings` is set
+pub fn generate_statusline_with_timings(
+    hook_data: &HookData,
+    statusline_config: &config::StatuslineConfig,
+    timings: bool,
+) -> Result<String> {
+    render_statusline(
+        hook_data,
+        statusline_config,
+        timings,
+        RenderHistory::default(),
+    )
+    .map(|r| r.output)
+}
+
+/// Render the statusline and report the block cost it shows.
+/// `history` comes from the last render and feeds the cost delta and 5h
+/// limit ETA; the updated history is returned with the output.
+pub fn render_statusline(
+    hook_data: &HookData,
+    statusline_config: &config::StatuslineConfig,
+    timings: bool,
+    history: RenderHistory,
+) -> Result<RenderedStatusline> {
+    // Parsed once here for both the block scan and the context calculation
+    let transcript = blocks::read_transcript(Path::new(&hook_data.transcript_path));
+    render_with_block(
+        hook_data,
+        statusline_config,
+        timings,
+        history,
+        transcript
+            .as_ref()
+            .map(|t| {
+                t.entries
+                    .as_slice()
+            }),
+        |pricing, statusline_config| {
+            let claude_paths = find_claude_paths_with(&statusline_config.extra_project_paths)?;
+            load_active_block_with(
+                &claude_paths,
+                pricing,
+                statusline_config,
+                transcript.as_ref(),
+            )
+        },
+    )
+}
+
+/// Render the statusline with the block built from `entries` (e.g. a
+/// transcript piped on stdin) instead of scanning Claude project directories
+pub fn render_statusline_from_entries(
+    hook_data: &HookData,
+    statusline_config: &config::StatuslineConfig,
+    timings: bool,
+    entries: &[UsageData],
+) -> Result<String> {
+    render_with_block(
+        hook_data,
+        statusline_config,
+        timings,
+        RenderHistory::default(),
+        Some(entries),
+        |pricing, statusline_config| {
+            blocks::latest_block_from_entries(
+                entries,
+                pricing,
+                statusline_config.block_duration_hours,
+            )
+        },
+    )
+    .map(|r| r.output)
+}
+
+fn render_with_block(
+    hook_data: &HookData,
+    statusline_config: &config::StatuslineConfig,
+    timings: bool,
+    mut history: RenderHistory,
+    transcript_entries: Option<&[UsageData]>,
+    load_block: impl FnOnce(&PricingFetcher, &StatuslineConfig) -> Result<Block>,
+) -> Result<RenderedStatusline> {
+    let mut timer = StageTimer::new(timings);
+    let statusline_config = &statusline_config.with_env_overrides();
+    let cache_dir = get_cache_dir()?;
+    create_cache_dir(&cache_dir)?;
+
+    let login = api_usage::get_login_info();
+    let plan_type = login.plan_type;
+    let thresholds = &statusline_config.thresholds;
+    let number_format = statusline_config.number_format();
+
+    let pricing = PricingFetcher::with_pruned_cache(
+        &cache_dir,
+        statusline_config
+            .cache
+            .pruned_pricing,
+        statusline_config.network_timeout(),
+    )?;
+    timer.stage("pricing load");
+    let block = load_block(&pricing, statusline_config)?;
+    timer.stage("block scan");
+
+    // Idle with hide_when_idle: only model and directory remain, so skip
+    // the API and update checks entirely
+    let hide_idle = statusline_config.hide_when_idle && !block.is_active;
+    let elements = statusline_config.visible_elements(block.is_active);
+
+    let api_result = if !hide_idle && statusline_config.needs_api() {
+        api_usage::fetch_usage(&statusline_config.fetch_options())
+    } else {
+        api_usage::ApiUsageResult::Unavailable
+    };
+    let api_usage = api_result
+        .data()
+        .cloned();
+    timer.stage("api fetch");
+    if let Some(api) = &api_usage {
+        history.record_usage(api.five_hour_percent, chrono::Utc::now().timestamp());
+    }
+
+    let burn_rate = calculate_burn_rate(
+        &block,
+        api_usage.as_ref(),
+        thresholds.burn_rate_show_ratio(),
+        statusline_config.burn_rate_window_minutes,
+    )?;
+    let context_info = context::calculate_context_with(
+        hook_data,
+        transcript_entries,
+        statusline_config.context_counts_cache_read,
+        statusline_config.context_show_remaining,
+    )?;
+    timer.stage("context calc");
+    let update_available = if hide_idle {
+        None
+    } else {
+        claude_update::check_update_available(statusline_config)
+    };
+    timer.stage("update check");
+
+    let hide_zero = statusline_config.hide_zero_segments;
+    let mut parts = Vec::new();
+    let mut api_metrics_emitted = false;
+    let mut burn_rate_emitted = false;
+
+    for element in &elements {
+        let parts_before = parts.len();
+        match element {
+            StatusElement::Model => {
+                let name = format_model_name(
+                    &hook_data.model,
+                    statusline_config.model_display,
+                    &statusline_config.model_fallback_name,
+                );
+                let project = if statusline_config.show_project_in_model {
+                    format_project_prefix(
+                        hook_data
+                            .workspace
+                            .as_ref(),
+                    )
+                } else {
+                    None
+                };
+                parts.push(format!("{}🤖{}", project.unwrap_or_default(), name));
+            }
+            StatusElement::BlockCost => {
+                let combined = statusline_config
+                    .show_cost_with_limit
+                    .then(|| {
+                        format_cost_and_limit(
+                            &block,
+                            api_usage.as_ref(),
+                            statusline_config.api_pct_decimals,
+                            number_format,
+                        )
+                    })
+                    .flatten();
+                let shows_combined = combined.is_some();
+                let Some(mut info) = combined.or_else(|| {
+                    format_block_info(
+                        &block,
+                        statusline_config.plan_mode,
+                        api_usage.as_ref(),
+                        hide_zero,
+                        statusline_config.api_pct_decimals,
+                        number_format,
+                    )
+                }) else {
+                    continue;
+                };
+                let shows_dollars = shows_combined
+                    || statusline_config.plan_mode == PlanMode::PayAsYouGo
+                    || api_usage.is_none();
+                if statusline_config.show_cost_delta
+                    && block.is_active
+                    && shows_dollars
+                    && let Some(delta) =
+                        format_cost_delta(history.last_cost_usd, block.cost_usd, number_format)
+                {
+                    info.push_str(&delta);
+                }
+                parts.push(format!("💰{}", info));
+            }
+            StatusElement::EffectiveRate => {
+                if let Some(rate) = format_effective_rate(
+                    &block,
+                    statusline_config.tokens_include_cache,
+                    number_format,
+                ) {
+                    parts.push(rate);
+                }
+            }
+            StatusElement::TimeRemaining5h => {
+                if let Some(time) = format_time_remaining_5h(
+                    &block,
+                    api_usage.as_ref(),
+                    plan_type,
+                    thresholds,
+                    statusline_config.time_show_clock,
+                ) {
+                    parts.push(time);
+                }
+            }
+            StatusElement::TimeRemaining7d => {
+                if let Some(time) = format_time_remaining_7d(
+                    api_usage.as_ref(),
+                    plan_type,
+                    statusline_config.reset_display,
+                    statusline_config
+                        .timezone
+                        .as_deref(),
+                ) {
+                    parts.push(time);
+                }
+            }
+            StatusElement::BlockWindow => {
+                if let Some(window) = format_block_window(
+                    &block,
+                    statusline_config
+                        .timezone
+                        .as_deref(),
+                    statusline_config.clock_24h,
+                ) {
+                    parts.push(window);
+                }
+            }
+            StatusElement::BurnRate | StatusElement::BurnRateEta => {
+                if !burn_rate_emitted {
+                    burn_rate_emitted = true;
+                    let enabled = &elements;
+                    let show_rate = enabled.contains(&StatusElement::BurnRate);
+                    let show_eta = enabled.contains(&StatusElement::BurnRateEta);
+                    let style = BurnRateStyle {
+                        show_rate,
+                        show_eta,
+                        ..statusline_config.burn_rate_style()
+                    };
+                    if let Some(s) = format_burn_rate_component(&burn_rate, plan_type, &style) {
+                        parts.push(s);
+                    }
+                }
+            }
+            StatusElement::Context => {
+                parts.push(format!(
+                    "🧠{}",
+                    format_context(
+                        context_info.as_ref(),
+                        thresholds,
+                        statusline_config.compact_tokens,
+                        statusline_config.context_show_remaining
+                    )
+                ));
+            }
+            StatusElement::Sessions => {
+                if let Some(sessions) = format_sessions(&block) {
+                    parts.push(sessions);
+                }
+            }
+            StatusElement::SessionCost => {
+                let cost = transcript_entries
+                    .map_or(0.0, |entries| blocks::session_cost(entries, &pricing));
+                if let Some(cost) = format_session_cost(cost, hide_zero, number_format) {
+                    parts.push(cost);
+                }
+            }
+            StatusElement::ApiMetrics5h
+            | StatusElement::ApiMetrics7d
+            | StatusElement::ApiMetricsSonnet => {
+                if !api_metrics_emitted {
+                    api_metrics_emitted = true;
+                    if let Some(label) = api_result.error_label() {
+                        parts.push(format!("📊({})", label));
+                    } else {
+                        let model = hook_data
+                            .model
+                            .id
+                            .as_deref()
+                            .unwrap_or(
+                                &hook_data
+                                    .model
+                                    .display_name,
+                            );
+                        let metrics = statusline_config.api_metric_order(&elements, model);
+                        let style = statusline_config.api_metric_style();
+                        let text = if statusline_config.compact_api_metrics {
+                            format_api_usage_compact(api_usage.as_ref(), &metrics, &style)
+                        } else {
+                            let pieces: Vec<String> = metrics
+                                .iter()
+                                .filter_map(|metric| {
+                                    format_api_metric(api_usage.as_ref(), *metric, &style)
+                                })
+                                .collect();
+                            (!pieces.is_empty())
+                                .then(|| pieces.join(&statusline_config.api_metrics_separator))
+                        };
+                        if let Some(text) = text {
+                            parts.push(format_api_segment(
+                                format!("📊{}", text),
+                                api_usage.as_ref(),
+                            ));
+                        }
+                    }
+                }
+            }
+            StatusElement::UpdateStable | StatusElement::UpdateLatest => {
+                if let Some((current, latest)) = &update_available {
+                    parts.push(format_update(
+                        &statusline_config.update_prefix,
+                        statusline_config
+                            .show_update_from
+                            .then_some(current.as_str()),
+                        latest,
+                    ));
+                }
+            }
+            StatusElement::LimitEta => {
+                if let Some(eta) =
+                    format_limit_eta(api_usage.as_ref(), &history, chrono::Utc::now())
+                {
+                    parts.push(eta);
+                }
+            }
+            StatusElement::TokenExpiry => {
+                if let Some(warning) =
+                    format_token_expiry(login.token_expiry, thresholds.token_expiry_warning_hours)
+                {
+                    parts.push(warning);
+                }
+            }
+            StatusElement::Directory => {
+                if let Some(workspace) = &hook_data.workspace {
+                    let color = statusline_config
+                        .element_color(element)
+                        .unwrap_or(AnsiColors::Green);
+                    parts.push(format_directory(Path::new(&workspace.current_dir), color));
+                }
+                continue;
+            }
+        }
+
+        // Static color override for elements not colored by thresholds
+        if parts.len() > parts_before
+            && !element.is_threshold_colored()
+            && let Some(color) = statusline_config.element_color(element)
+            && let Some(part) = parts.last_mut()
+        {
+            *part = part
+                .color(color)
+                .to_string();
+        }
+    }
+
+    if statusline_config.show_data_source
+        && let Some(source) = format_data_source(
+            &block,
+            paths::home_dir()
+                .ok()
+                .as_deref(),
+        )
+    {
+        parts.push(source);
+    }
+
+    if statusline_config.show_pricing_stale && pricing.is_stale() {
+        parts.push("💲!".to_string());
+    }
+
+    let output = join_segments(&parts, multiline());
+    timer.stage("render");
+    let output = if statusline_config.show_emojis {
+        output
+    } else {
+        strip_emojis(&output)
+    };
+    let output = match statusline_config.max_length {
+        Some(max_length) => truncate_visible(&output, max_length),
+        None => output,
+    };
+    if let Some(step) = statusline_config.alert_cost_usd
+        && block.is_active
+        && let Some(boundary) = alert::crossed_boundary(history.last_cost_usd, block.cost_usd, step)
+        && alert::claim_alert(&cache_dir, block.start_time, boundary)
+    {
+        alert::notify_cost_crossed(boundary, block.cost_usd, number_format);
+    }
+    history.last_cost_usd = block
+        .is_active
+        .then_some(block.cost_usd);
+    Ok(RenderedStatusline { output, history })
+}
@@ -0,0 +1,147 @@
+use crate::paths::iter_jsonl_files;
+use crate::pricing::PricingFetcher;
+use crate::types::UsageData;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const LIFETIME_CACHE_FILE: &str = "lifetime-cost.json";
+
+/// All-time cost across every transcript, cached by newest file mtime
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifetimeCost {
+    pub newest_mtime: u64,
+    pub file_count: usize,
+    pub entry_count: usize,
+    pub cost_usd: f64,
+}
+
+/// Newest mtime (unix seconds) among `files`
+fn newest_mtime(files: &[PathBuf]) -> u64 {
+    files
+        .iter()
+        .filter_map(|path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+        })
+        .filter_map(|t| {
+            t.duration_since(UNIX_EPOCH)
+                .ok()
+        })
+        .map(|d| d.as_secs())
+        .max()
+        .unwrap_or(0)
+}
+
+fn read_cache(cache_dir: &Path) -> Option<LifetimeCost> {
+    let contents = fs::read_to_string(cache_dir.join(LIFETIME_CACHE_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Sum the cost of every deduplicated entry in all transcripts. This is a full
+/// scan with no lookback window; the result is reused until a file changes.
+pub fn calculate_lifetime_cost(
+    claude_paths: &[PathBuf],
+    pricing: &PricingFetcher,
+    cache_dir: &Path,
+) -> Result<LifetimeCost> {
+    let files = iter_jsonl_files(claude_paths)?;
+    let newest = newest_mtime(&files);
+
+    if let Some(cached) = read_cache(cache_dir)
+        && cached.newest_mtime == newest
+        && cached.file_count == files.len()
+    {
+        return Ok(cached);
+    }
+
+    let mut processed_hashes: HashSet<String> = HashSet::new();
+    let mut entry_count = 0;
+    let mut cost_usd = 0.0;
+
+    for session_file in &files {
+        let reader = BufReader::new(File::open(session_file)?);
+        for line in reader.lines() {
+            let line = line?;
+            if line
+                .trim()
+                .is_empty()
+            {
+                continue;
+            }
+            if let Ok(entry) = serde_json::from_str::<UsageData>(&line)
+                && entry.has_usage()
+            {
+                if let Some(hash) = entry.dedup_key()
+                    && !processed_hashes.insert(hash)
+                {
+                    continue;
+                }
+                entry_count += 1;
+                cost_usd += pricing.calculate_entry_cost(&entry);
+            }
+        }
+    }
+
+    let result = LifetimeCost {
+        newest_mtime: newest,
+        file_count: files.len(),
+        entry_count,
+        cost_usd,
+    };
+
+    if let Ok(json) = serde_json::to_string(&result) {
+        let _ = fs::write(cache_dir.join(LIFETIME_CACHE_FILE), json);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_lifetime_cost_dedups_and_caches() {
+        let base = std::env::temp_dir().join("ccusage-test-lifetime");
+        let project = base
+            .join("projects")
+            .join("project");
+        let cache_dir = base.join("cache");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&cache_dir).unwrap();
+
+        let line = |id: &str| {
+            format!(
+                r#"{{"timestamp":"2024-01-01T10:00:00Z","requestId":"{}","message":{{"id":"{}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":100000,"output_tokens":0}}}}}}"#,
+                id, id
+            )
+        };
+        fs::write(
+            project.join("a.jsonl"),
+            format!("{}\n{}\n", line("1"), line("2")),
+        )
+        .unwrap();
+        fs::write(project.join("b.jsonl"), line("2")).unwrap();
+
+        let pricing = PricingFetcher::from_models(HashMap::new());
+        let paths = vec![base.join("projects")];
+        let first = calculate_lifetime_cost(&paths, &pricing, &cache_dir).unwrap();
+        let cached = read_cache(&cache_dir);
+        let second = calculate_lifetime_cost(&paths, &pricing, &cache_dir).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(first.entry_count, 2);
+        assert_eq!(first.file_count, 2);
+        // Fallback Sonnet 4 pricing: $3/M input tokens, 100k per entry
+        assert!((first.cost_usd - 0.6).abs() < 1e-9);
+        assert_eq!(cached, Some(first.clone()));
+        assert_eq!(second, first);
+    }
+}
@@ -1,30 +1,31 @@
-mod api_usage;
-mod blocks;
-mod burn_rate;
-mod cache;
-mod claude_binary;
-mod claude_update;
-mod config;
-mod context;
-mod format;
 mod install;
-mod paths;
-mod pricing;
-mod types;
 
 use anyhow::{Context, Result};
-use blocks::find_active_block;
-use burn_rate::calculate_burn_rate;
-use cache::{cleanup_stale_locks, get_cache_dir, try_get_cached, update_cache};
-use clap::{Parser, Subcommand};
-use config::StatusElement;
-use context::calculate_context;
-use format::*;
-use paths::{find_claude_paths, iter_jsonl_files};
-use pricing::PricingFetcher;
+use ccusage_statusline_rs::api_usage;
+use ccusage_statusline_rs::blocks::{blocks_since, read_usage_entries, transcript_stats};
+use ccusage_statusline_rs::burn_rate::calculate_burn_rate;
+use ccusage_statusline_rs::cache::{
+    cleanup_stale_locks, create_cache_dir, get_cache_dir, get_or_render, set_force_refresh,
+    write_output_file,
+};
+use ccusage_statusline_rs::config::{self, StatusElement};
+use ccusage_statusline_rs::context;
+use ccusage_statusline_rs::csv_export;
+use ccusage_statusline_rs::format::*;
+use ccusage_statusline_rs::lifetime;
+use ccusage_statusline_rs::paths::{self, find_claude_paths_with, iter_jsonl_files};
+use ccusage_statusline_rs::pricing::PricingFetcher;
+use ccusage_statusline_rs::types::{self, HookData};
+use ccusage_statusline_rs::{
+    StageTimer, find_active_block, generate_statusline_with_timings, load_active_block,
+    render_statusline, render_statusline_from_entries, wait_for_notifications,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, IsTerminal, Read};
-use types::HookData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Parser)]
 #[command(name = "ccusage-statusline-rs")]
@@ -33,18 +34,131 @@ use types::HookData;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Print how long each render stage took to stderr
+    #[arg(long, global = true)]
+    timings: bool,
+
+    /// Color output: always, auto (only when stdout is a terminal) or never.
+    /// Without the flag, colors are always emitted unless NO_COLOR is set.
+    #[arg(long, global = true, value_enum)]
+    color: Option<ColorChoice>,
+
+    /// Also write each rendered line to this file (atomically replaced)
+    #[arg(long, global = true)]
+    output_file: Option<PathBuf>,
+
+    /// Put each segment on its own line instead of joining them with ' │ '
+    #[arg(long, global = true)]
+    multiline: bool,
+
+    /// Ignore the output, usage API, pricing and update-check caches for this
+    /// run, refetching everything and writing the fresh results back
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    /// Account profile: reads ~/.claude/.credentials-<name>.json and ~/.claude-<name>/projects
+    #[arg(long, global = true)]
+    profile: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum ColorChoice {
+    Always,
+    Auto,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to emit colors; `Auto` follows whether stdout is a terminal
+    fn enabled(self, stdout_is_terminal: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Auto => stdout_is_terminal,
+            Self::Never => false,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Install statusLine configuration (respects CLAUDE_CONFIG_DIR)
-    Install,
+    Install {
+        /// Show the statusLine change without writing settings.json
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Remove statusLine configuration (respects CLAUDE_CONFIG_DIR)
-    Uninstall,
+    Uninstall {
+        /// Show the statusLine change without writing settings.json
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Test the statusline with most recent transcript
-    Test,
+    Test {
+        /// Model id to report in the synthetic hook data (e.g. claude-opus-4-6)
+        #[arg(long)]
+        model: Option<String>,
+    },
+    /// Print sample hook JSON for the most recent transcript (pipe into this binary)
+    SampleInput {
+        /// Model id to report in the sample (e.g. claude-opus-4-6)
+        #[arg(long)]
+        model: Option<String>,
+    },
     /// Configure statusline elements (enable/disable and reorder)
     Config,
+    /// Query the usage API once (no cache) and print the result or HTTP error
+    ApiCheck,
+    /// Show all-time cost across every transcript (full scan, cached)
+    Total,
+    /// Render the statusline from a single JSONL transcript instead of the
+    /// Claude project directories (for golden-output tests)
+    Render {
+        /// Transcript to read usage from, or '-' for stdin
+        #[arg(long)]
+        transcript: String,
+        /// Model id to report in the synthetic hook data (e.g. claude-opus-4-6)
+        #[arg(long)]
+        model: Option<String>,
+    },
+    /// Time full block scans of a fixture projects directory (no network)
+    #[command(hide = true)]
+    Bench {
+        /// Directory laid out like ~/.claude/projects (one subdir per project)
+        dir: PathBuf,
+        /// Number of timed scans
+        #[arg(long, default_value_t = 20)]
+        iterations: usize,
+    },
+    /// Write the blocks of the last N days, or of --since, as CSV to stdout
+    ExportCsv {
+        /// Number of trailing days to scan
+        #[arg(long, default_value_t = 30)]
+        days: u64,
+        /// Trailing window to scan instead of --days (e.g. 3d, 12h, 90m)
+        #[arg(long, conflicts_with = "days", value_parser = parse_since)]
+        since: Option<chrono::Duration>,
+    },
+    /// Check that a JSONL transcript parses as usage entries, exiting non-zero
+    /// when none do
+    Validate {
+        /// Transcript to check
+        file: PathBuf,
+    },
+    /// Print only the active block cost as a bare number (for shell prompts)
+    Cost {
+        /// Prefix the number with this symbol (e.g. '$')
+        #[arg(long)]
+        currency_symbol: Option<String>,
+    },
+    /// Print only the context percentage as a bare integer (for shell
+    /// prompts), from the hook JSON on stdin or --transcript
+    Context {
+        /// Transcript to measure instead of the one named in the hook JSON
+        #[arg(long)]
+        transcript: Option<PathBuf>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -53,24 +167,51 @@ fn main() -> Result<()> {
         .expect("failed to install rustls crypto provider");
 
     let cli = Cli::parse();
-
-    match cli.command {
-        Some(Commands::Install) => install::install(),
-        Some(Commands::Uninstall) => install::uninstall(),
-        Some(Commands::Test) => run_test_mode(),
+    if let Some(name) = &cli.profile {
+        paths::set_profile(name)?;
+    }
+    if let Some(choice) = cli.color {
+        set_color_override(choice.enabled(io::stdout().is_terminal()));
+    }
+    set_multiline(cli.multiline);
+    set_force_refresh(cli.refresh);
+
+    let output_file = cli
+        .output_file
+        .as_deref();
+    let result = match cli.command {
+        Some(Commands::Install { dry_run }) => install::install(dry_run),
+        Some(Commands::Uninstall { dry_run }) => install::uninstall(dry_run),
+        Some(Commands::Test { model }) => run_test_mode(model, cli.timings, output_file),
+        Some(Commands::SampleInput { model }) => run_sample_input_mode(model),
         Some(Commands::Config) => config::run_config_menu(),
+        Some(Commands::ApiCheck) => run_api_check_mode(),
+        Some(Commands::Total) => run_total_mode(),
+        Some(Commands::Render { transcript, model }) => {
+            run_render_mode(&transcript, model, cli.timings, output_file)
+        }
+        Some(Commands::Bench { dir, iterations }) => run_bench_mode(dir, iterations),
+        Some(Commands::ExportCsv { days, since }) => {
+            run_export_csv_mode(since.unwrap_or_else(|| chrono::Duration::days(days as i64)))
+        }
+        Some(Commands::Cost { currency_symbol }) => run_cost_mode(currency_symbol.as_deref()),
+        Some(Commands::Context { transcript }) => run_context_mode(transcript.as_deref()),
+        Some(Commands::Validate { file }) => run_validate_mode(&file),
         None => {
             let stdin = io::stdin();
             if stdin.is_terminal() {
-                run_interactive_mode()
+                run_interactive_mode(output_file)
             } else {
-                run_piped_mode()
+                run_piped_mode(cli.timings, output_file)
             }
         }
-    }
+    };
+    // Cost alerts are sent in the background while the output is printed
+    wait_for_notifications();
+    result
 }
 
-fn run_piped_mode() -> Result<()> {
+fn run_piped_mode(timings: bool, output_file: Option<&Path>) -> Result<()> {
     let mut input = String::new();
     io::stdin()
         .read_to_string(&mut input)
@@ -84,9 +225,15 @@ fn run_piped_mode() -> Result<()> {
     let hook_data: HookData = serde_json::from_str(&input).context("Failed to parse JSON input")?;
 
     let cache_dir = get_cache_dir()?;
-    fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
+    create_cache_dir(&cache_dir)?;
 
-    let cache_path = cache_dir.join(format!("{}.lock", hook_data.session_id));
+    // Single-line and multiline renders are cached separately
+    let cache_name = if multiline() {
+        format!("{}-multiline.lock", hook_data.session_id)
+    } else {
+        format!("{}.lock", hook_data.session_id)
+    };
+    let cache_path = cache_dir.join(cache_name);
 
     let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
     cleanup_stale_locks(
@@ -96,34 +243,35 @@ fn run_piped_mode() -> Result<()> {
             .output_cache_secs,
     );
 
-    if let Some(cached) = try_get_cached(
+    // Everything that may touch the network runs inside the render closure,
+    // so a cache hit returns before pricing, API or update checks
+    let mut timer = StageTimer::new(timings);
+    let mut rendered = false;
+    let output = get_or_render(
         &cache_path,
         &hook_data.transcript_path,
-        statusline_config
-            .cache
-            .output_cache_secs,
-    )? {
-        println!("{}", cached);
-        return Ok(());
-    }
-
-    let output = generate_statusline(&hook_data, &statusline_config)?;
-    println!("{}", output);
-
-    update_cache(&cache_path, &hook_data.transcript_path, &output)?;
-
-    Ok(())
+        &statusline_config.cache,
+        |history| {
+            timer.stage("cache lookup");
+            rendered = true;
+            let result = render_statusline(&hook_data, &statusline_config, timings, history);
+            timer.restart();
+            result
+        },
+    )?;
+    timer.stage(if rendered { "cache write" } else { "cache hit" });
+    emit(&output, output_file)
 }
 
-fn run_interactive_mode() -> Result<()> {
+fn run_interactive_mode(output_file: Option<&Path>) -> Result<()> {
     let cache_dir = get_cache_dir()?;
-    fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
+    create_cache_dir(&cache_dir)?;
 
     let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
     let thresholds = &statusline_config.thresholds;
     let plan_type = api_usage::get_plan_type();
     let api_result = if statusline_config.needs_api() {
-        api_usage::fetch_usage(&statusline_config.cache)
+        api_usage::fetch_usage(&statusline_config.fetch_options())
     } else {
         api_usage::ApiUsageResult::Unavailable
     };
@@ -131,50 +279,157 @@ fn run_interactive_mode() -> Result<()> {
         .data()
         .cloned();
 
-    let pricing = PricingFetcher::new(&cache_dir)?;
-    let claude_paths = find_claude_paths()?;
-    let block = find_active_block(&claude_paths, &pricing)?;
+    let pricing = PricingFetcher::with_pruned_cache(
+        &cache_dir,
+        statusline_config
+            .cache
+            .pruned_pricing,
+        statusline_config.network_timeout(),
+    )?;
+    let claude_paths = find_claude_paths_with(&statusline_config.extra_project_paths)?;
+    let block = load_active_block(&claude_paths, &pricing, &statusline_config)?;
     let burn_rate = calculate_burn_rate(
         &block,
         api_usage.as_ref(),
         thresholds.burn_rate_show_ratio(),
+        statusline_config.burn_rate_window_minutes,
     )?;
 
+    // No hook data here; `CCUSAGE_TRANSCRIPT` opts into the context segment
+    let context_info = context::context_from_env_transcript(
+        std::env::var_os("CCUSAGE_TRANSCRIPT"),
+        statusline_config.context_counts_cache_read,
+    );
+
+    let hide_zero = statusline_config.hide_zero_segments;
     let mut parts = Vec::new();
 
-    parts.push(format!("💰{}", format_block_info(&block)));
+    if let Some(info) = format_block_info(
+        &block,
+        statusline_config.plan_mode,
+        api_usage.as_ref(),
+        hide_zero,
+        statusline_config.api_pct_decimals,
+        statusline_config.number_format(),
+    ) {
+        parts.push((StatusElement::BlockCost, format!("💰{}", info)));
+    }
 
-    if let Some(time) = format_time_remaining_5h(&block, api_usage.as_ref(), plan_type) {
-        parts.push(time);
+    if let Some(time) = format_time_remaining_5h(
+        &block,
+        api_usage.as_ref(),
+        plan_type,
+        thresholds,
+        statusline_config.time_show_clock,
+    ) {
+        parts.push((StatusElement::TimeRemaining5h, time));
     }
 
-    if let Some(s) = format_burn_rate_component(&burn_rate, plan_type, true, false, thresholds) {
-        parts.push(s);
+    if let Some(s) =
+        format_burn_rate_component(&burn_rate, plan_type, &statusline_config.burn_rate_style())
+    {
+        parts.push((StatusElement::BurnRate, s));
     }
 
     if let Some(label) = api_result.error_label() {
-        parts.push(format!("📊({})", label));
-    } else if let Some(api) = format_api_usage_5h(api_usage.as_ref()) {
-        parts.push(format!("📊{}", api));
-        if let Some(api) = format_api_usage_7d(api_usage.as_ref()) {
-            parts.push(api);
+        parts.push((StatusElement::ApiMetrics5h, format!("📊({})", label)));
+    } else if let Some(api) = format_api_usage_5h(
+        api_usage.as_ref(),
+        statusline_config.spark_mode,
+        hide_zero,
+        statusline_config.api_pct_decimals,
+        statusline_config.five_hour_reset_clock(),
+        thresholds,
+    ) {
+        parts.push((
+            StatusElement::ApiMetrics5h,
+            format_api_segment(format!("📊{}", api), api_usage.as_ref()),
+        ));
+        if let Some(api) = format_api_usage_7d(
+            api_usage.as_ref(),
+            hide_zero,
+            statusline_config.api_pct_decimals,
+            thresholds,
+        ) {
+            parts.push((StatusElement::ApiMetrics7d, api));
         }
     }
 
-    let output = parts.join(" │ ");
-    if statusline_config.show_emojis {
-        println!("{}", output);
-    } else {
-        println!("{}", strip_emojis(&output));
+    if context_info.is_some() {
+        parts.push((
+            StatusElement::Context,
+            format!(
+                "🧠{}",
+                format_context(
+                    context_info.as_ref(),
+                    thresholds,
+                    statusline_config.compact_tokens,
+                    statusline_config.context_show_remaining
+                )
+            ),
+        ));
     }
 
+    let parts = in_element_order(parts, &statusline_config.enabled_elements);
+    let output = join_segments(&parts, multiline());
+    let output = if statusline_config.show_emojis {
+        output
+    } else {
+        strip_emojis(&output)
+    };
+    let output = match statusline_config.max_length {
+        Some(max_length) => truncate_visible(&output, max_length),
+        None => output,
+    };
+    emit(&output, output_file)
+}
+
+/// Segment texts sorted by their element's position in `order`; elements
+/// missing from it go last, keeping their relative order
+fn in_element_order(
+    mut parts: Vec<(StatusElement, String)>,
+    order: &[StatusElement],
+) -> Vec<String> {
+    parts.sort_by_key(|(element, _)| {
+        order
+            .iter()
+            .position(|e| e == element)
+            .unwrap_or(usize::MAX)
+    });
+    parts
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect()
+}
+
+/// Print the rendered line, mirroring it to `--output-file` when given
+fn emit(output: &str, output_file: Option<&Path>) -> Result<()> {
+    // Cached renders may carry colors from before `--color=never`
+    let stripped;
+    let output = if colors_forced_off() {
+        stripped = strip_ansi(output);
+        &stripped
+    } else {
+        output
+    };
+    println!("{}", output);
+    if let Some(path) = output_file {
+        write_output_file(path, output)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
     Ok(())
 }
 
-fn run_test_mode() -> Result<()> {
-    let claude_paths = find_claude_paths()?;
+/// Build hook data for the most recently modified transcript, as Claude Code would send it
+fn sample_hook_data(model: Option<String>) -> Result<HookData> {
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    let claude_paths = find_claude_paths_with(&statusline_config.extra_project_paths)?;
+    sample_hook_data_from(&claude_paths, model)
+}
 
-    let most_recent = iter_jsonl_files(&claude_paths)?
+/// Like `sample_hook_data`, picking the transcript under `claude_paths`
+fn sample_hook_data_from(claude_paths: &[PathBuf], model: Option<String>) -> Result<HookData> {
+    let most_recent = iter_jsonl_files(claude_paths)?
         .into_iter()
         .filter_map(|path| {
             fs::metadata(&path)
@@ -190,16 +445,16 @@ fn run_test_mode() -> Result<()> {
     let (transcript_path, _) =
         most_recent.context("No .jsonl files found in Claude directories")?;
 
-    eprintln!("Testing with: {}", transcript_path.display());
-
-    let hook_data = HookData {
+    Ok(HookData {
         session_id: "test-session".to_string(),
         transcript_path: transcript_path
             .to_string_lossy()
             .to_string(),
         model: types::ModelInfo {
-            id: None,
-            display_name: "Claude 3.5 Sonnet".to_string(),
+            display_name: model
+                .as_deref()
+                .map_or_else(|| "Claude 3.5 Sonnet".to_string(), model_display_name),
+            id: model,
         },
         workspace: Some(types::Workspace {
             current_dir: std::env::current_dir()?
@@ -207,167 +462,466 @@ fn run_test_mode() -> Result<()> {
                 .to_string(),
         }),
         context_window: None,
+    })
+}
+
+fn run_test_mode(model: Option<String>, timings: bool, output_file: Option<&Path>) -> Result<()> {
+    let hook_data = sample_hook_data(model)?;
+    eprintln!("Testing with: {}", hook_data.transcript_path);
+
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    let output = generate_statusline_with_timings(&hook_data, &statusline_config, timings)?;
+    emit(&output, output_file)
+}
+
+fn run_render_mode(
+    transcript: &str,
+    model: Option<String>,
+    timings: bool,
+    output_file: Option<&Path>,
+) -> Result<()> {
+    let entries = if transcript == "-" {
+        read_usage_entries(io::stdin().lock())?
+    } else {
+        let file = fs::File::open(transcript)
+            .with_context(|| format!("Failed to open transcript {}", transcript))?;
+        read_usage_entries(io::BufReader::new(file))?
+    };
+
+    // Default to the model of the newest assistant message in the transcript
+    let model = model.or_else(|| {
+        entries
+            .iter()
+            .rev()
+            .find_map(|e| {
+                e.message
+                    .model
+                    .clone()
+            })
+    });
+    let hook_data = HookData {
+        session_id: "render".to_string(),
+        transcript_path: transcript.to_string(),
+        model: types::ModelInfo {
+            display_name: model
+                .as_deref()
+                .map_or_else(|| "Claude".to_string(), model_display_name),
+            id: model,
+        },
+        workspace: None,
+        context_window: None,
     };
 
     let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
-    let output = generate_statusline(&hook_data, &statusline_config)?;
-    println!("{}", output);
+    let output = render_statusline_from_entries(&hook_data, &statusline_config, timings, &entries)?;
+    emit(&output, output_file)
+}
+
+fn run_api_check_mode() -> Result<()> {
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    match api_usage::check_api(&statusline_config.fetch_options()) {
+        Ok(data) => {
+            println!("{:#?}", data);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("API check failed: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_total_mode() -> Result<()> {
+    let cache_dir = get_cache_dir()?;
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    let pricing = PricingFetcher::with_pruned_cache(
+        &cache_dir,
+        statusline_config
+            .cache
+            .pruned_pricing,
+        statusline_config.network_timeout(),
+    )?;
+    let claude_paths = find_claude_paths_with(&statusline_config.extra_project_paths)?;
+
+    eprintln!("Scanning all transcripts, this may take a while...");
+    let total = lifetime::calculate_lifetime_cost(&claude_paths, &pricing, &cache_dir)?;
+
+    println!(
+        "💰 Lifetime cost: {} ({} entries in {} files)",
+        format_currency(total.cost_usd, statusline_config.number_format()),
+        total.entry_count,
+        total.file_count
+    );
 
     Ok(())
 }
 
-/// Generate statusline output
-fn generate_statusline(
-    hook_data: &HookData,
-    statusline_config: &config::StatuslineConfig,
-) -> Result<String> {
+/// Parse a `--since` window such as `3d`, `12h`, `90m` or `1h30m`
+fn parse_since(value: &str) -> Result<chrono::Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid duration '{}': expected numbers followed by s, m, h, d or w (e.g. 3d, 12h, 90m)",
+            value
+        )
+    };
+
+    let mut total = chrono::Duration::zero();
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let count: i64 = rest[..digits]
+            .parse()
+            .map_err(|_| invalid())?;
+        let unit = rest[digits..]
+            .chars()
+            .next()
+            .ok_or_else(invalid)?;
+        let part = match unit {
+            's' => chrono::Duration::try_seconds(count),
+            'm' => chrono::Duration::try_minutes(count),
+            'h' => chrono::Duration::try_hours(count),
+            'd' => chrono::Duration::try_days(count),
+            'w' => chrono::Duration::try_weeks(count),
+            _ => None,
+        };
+        total = part
+            .and_then(|part| total.checked_add(&part))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+
+    if total <= chrono::Duration::zero() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Blocks of the trailing `window` as CSV on stdout
+fn run_export_csv_mode(window: chrono::Duration) -> Result<()> {
     let cache_dir = get_cache_dir()?;
-    fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    let pricing = PricingFetcher::with_pruned_cache(
+        &cache_dir,
+        statusline_config
+            .cache
+            .pruned_pricing,
+        statusline_config.network_timeout(),
+    )?;
+    let claude_paths = find_claude_paths_with(&statusline_config.extra_project_paths)?;
+    let blocks = blocks_since(
+        &claude_paths,
+        &pricing,
+        statusline_config.block_duration_hours,
+        window,
+    )?;
 
-    let plan_type = api_usage::get_plan_type();
-    let thresholds = &statusline_config.thresholds;
-    let api_result = if statusline_config.needs_api() {
-        api_usage::fetch_usage(&statusline_config.cache)
+    csv_export::write_blocks_csv(&blocks, io::stdout().lock())?;
+    Ok(())
+}
+
+/// Summarize how a transcript parses, to tell a format mismatch from a
+/// missing transcript when context shows N/A
+fn run_validate_mode(file: &Path) -> Result<()> {
+    let reader = io::BufReader::new(
+        fs::File::open(file).with_context(|| format!("Failed to open {}", file.display()))?,
+    );
+    let stats = transcript_stats(reader)?;
+
+    println!("📄 {}", file.display());
+    println!("  Lines:         {}", stats.lines);
+    println!("  Parsed:        {}", stats.parsed);
+    println!("  With usage:    {}", stats.with_usage);
+    println!("  Without usage: {}", stats.without_usage());
+    match &stats.last_usage {
+        Some(usage) => println!(
+            "  Last usage:    input {}, output {}, cache write {}, cache read {}",
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens
+        ),
+        None => println!("  Last usage:    none"),
+    }
+
+    if stats.parsed == 0 {
+        anyhow::bail!("No lines of {} parse as usage entries", file.display());
+    }
+    Ok(())
+}
+
+/// Fast path: active block cost only, no context/API/update work
+fn run_cost_mode(currency_symbol: Option<&str>) -> Result<()> {
+    let cache_dir = get_cache_dir()?;
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    let pricing = PricingFetcher::with_pruned_cache(
+        &cache_dir,
+        statusline_config
+            .cache
+            .pruned_pricing,
+        statusline_config.network_timeout(),
+    )?;
+    let claude_paths = find_claude_paths_with(&statusline_config.extra_project_paths)?;
+    let block = load_active_block(&claude_paths, &pricing, &statusline_config)?;
+
+    let cost = if block.is_active { block.cost_usd } else { 0.0 };
+    println!("{}{:.2}", currency_symbol.unwrap_or(""), cost);
+
+    Ok(())
+}
+
+/// Fast path: context percentage only, no block/API/update work
+fn run_context_mode(transcript: Option<&Path>) -> Result<()> {
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    let stdin = io::stdin();
+    let hook_input = if transcript.is_none() && !stdin.is_terminal() {
+        let mut input = String::new();
+        stdin
+            .lock()
+            .read_to_string(&mut input)
+            .context("Failed to read stdin")?;
+        Some(input)
     } else {
-        api_usage::ApiUsageResult::Unavailable
+        None
     };
-    let api_usage = api_result
-        .data()
-        .cloned();
 
-    let pricing = PricingFetcher::new(&cache_dir)?;
-    let claude_paths = find_claude_paths()?;
-    let block = find_active_block(&claude_paths, &pricing)?;
-    let burn_rate = calculate_burn_rate(
-        &block,
-        api_usage.as_ref(),
-        thresholds.burn_rate_show_ratio(),
+    let info = resolve_context(
+        transcript,
+        hook_input.as_deref(),
+        statusline_config.context_counts_cache_read,
     )?;
-    let context_info = calculate_context(hook_data)?;
-    let update_available = claude_update::check_update_available();
+    println!("{}", info.percentage);
 
-    let mut parts = Vec::new();
-    let mut api_metrics_emitted = false;
-    let mut burn_rate_emitted = false;
+    Ok(())
+}
 
-    for element in &statusline_config.enabled_elements {
-        match element {
-            StatusElement::Model => {
-                let name = hook_data
-                    .model
-                    .display_name
-                    .replace(" context)", ")");
-                parts.push(format!("🤖{}", name));
-            }
-            StatusElement::BlockCost => {
-                parts.push(format!("💰{}", format_block_info(&block)));
-            }
-            StatusElement::TimeRemaining5h => {
-                if let Some(time) = format_time_remaining_5h(&block, api_usage.as_ref(), plan_type)
-                {
-                    parts.push(time);
-                }
-            }
-            StatusElement::TimeRemaining7d => {
-                if let Some(time) = format_time_remaining_7d(api_usage.as_ref(), plan_type) {
-                    parts.push(time);
-                }
-            }
-            StatusElement::BurnRate | StatusElement::BurnRateEta => {
-                if !burn_rate_emitted {
-                    burn_rate_emitted = true;
-                    let enabled = &statusline_config.enabled_elements;
-                    let show_rate = enabled.contains(&StatusElement::BurnRate);
-                    let show_eta = enabled.contains(&StatusElement::BurnRateEta);
-                    if let Some(s) = format_burn_rate_component(
-                        &burn_rate, plan_type, show_rate, show_eta, thresholds,
-                    ) {
-                        parts.push(s);
-                    }
-                }
-            }
-            StatusElement::Context => {
-                parts.push(format!(
-                    "🧠{}",
-                    format_context(context_info.as_ref(), thresholds)
-                ));
-            }
-            StatusElement::ApiMetrics5h
-            | StatusElement::ApiMetrics7d
-            | StatusElement::ApiMetricsSonnet => {
-                if !api_metrics_emitted {
-                    api_metrics_emitted = true;
-                    if let Some(label) = api_result.error_label() {
-                        parts.push(format!("📊({})", label));
-                    } else {
-                        let enabled = &statusline_config.enabled_elements;
-                        let mut api_parts = Vec::new();
-
-                        if enabled.contains(&StatusElement::ApiMetrics5h)
-                            && let Some(text) = format_api_usage_5h(api_usage.as_ref())
-                        {
-                            api_parts.push(format!("📊{}", text));
-                        }
-                        if enabled.contains(&StatusElement::ApiMetrics7d)
-                            && let Some(text) = format_api_usage_7d(api_usage.as_ref())
-                        {
-                            if api_parts.is_empty() {
-                                api_parts.push(format!("📊{}", text));
-                            } else {
-                                api_parts.push(text);
-                            }
-                        }
-                        if enabled.contains(&StatusElement::ApiMetricsSonnet)
-                            && let Some(text) = format_api_usage_sonnet(api_usage.as_ref())
-                        {
-                            if api_parts.is_empty() {
-                                api_parts.push(format!("📊{}", text));
-                            } else {
-                                api_parts.push(text);
-                            }
-                        }
-                        if !api_parts.is_empty() {
-                            parts.push(api_parts.join(" "));
-                        }
-                    }
-                }
-            }
-            StatusElement::UpdateStable | StatusElement::UpdateLatest => {
-                if let Some(ref new_version) = update_available {
-                    parts.push(format!("🔼{}", new_version));
-                }
-            }
-            StatusElement::Directory => {
-                if let Some(workspace) = &hook_data.workspace {
-                    parts.push(format_directory(&workspace.current_dir));
-                }
-            }
+/// Context of `transcript`, or of the transcript named by the `hook_input`
+/// JSON. Fails when neither leads to a readable transcript.
+fn resolve_context(
+    transcript: Option<&Path>,
+    hook_input: Option<&str>,
+    count_cache_read: bool,
+) -> Result<types::ContextInfo> {
+    let info = match (transcript, hook_input) {
+        (Some(path), _) => context::context_from_transcript_file(path, count_cache_read),
+        (None, Some(input)) => {
+            let hook_data: HookData =
+                serde_json::from_str(input).context("Failed to parse JSON input")?;
+            context::calculate_context_with(&hook_data, None, count_cache_read, false)?
+        }
+        (None, None) => {
+            anyhow::bail!("No transcript: pass --transcript or pipe the statusline hook JSON")
         }
+    };
+    info.context("Transcript not found or unreadable")
+}
+
+/// Time `iterations` JSONL block scans of `dir` and print min/median/max.
+/// Costs use the built-in fallback prices, so nothing is fetched and only
+/// the scan is measured.
+fn run_bench_mode(dir: PathBuf, iterations: usize) -> Result<()> {
+    if iterations == 0 {
+        anyhow::bail!("--iterations must be at least 1");
+    }
+    if !dir.is_dir() {
+        anyhow::bail!("Fixture directory not found: {}", dir.display());
     }
 
-    let output = parts.join(" │ ");
-    if statusline_config.show_emojis {
-        Ok(output)
-    } else {
-        Ok(strip_emojis(&output))
+    let statusline_config = config::StatuslineConfig::load().unwrap_or_default();
+    // Entries are priced per model either way, so the scan work is the same
+    let pricing = PricingFetcher::from_models(HashMap::new());
+    let claude_paths = [dir];
+
+    let scan = || find_active_block(&claude_paths, &pricing, &statusline_config.scan_options());
+
+    // Warm-up scan so the page cache is hot for every timed run
+    let block = scan()?;
+    let mut durations: Vec<Duration> = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        scan()?;
+        durations.push(start.elapsed());
     }
+    durations.sort();
+
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    println!(
+        "{} scans: min {:.2}ms, median {:.2}ms, max {:.2}ms (block active: {}, {} tokens)",
+        iterations,
+        ms(durations[0]),
+        ms(durations[iterations / 2]),
+        ms(durations[iterations - 1]),
+        block.is_active,
+        block.total_tokens
+    );
+
+    Ok(())
+}
+
+fn run_sample_input_mode(model: Option<String>) -> Result<()> {
+    let hook_data = sample_hook_data(model)?;
+    println!("{}", serde_json::to_string_pretty(&hook_data)?);
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sample_hook_data_round_trips() {
+        let base = std::env::temp_dir().join("ccusage-test-sample-hook");
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+        let older = project.join("older.jsonl");
+        let newer = project.join("newer.jsonl");
+        fs::write(&older, "{}\n").unwrap();
+        fs::write(&newer, "{}\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&older)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        let hook_data = sample_hook_data_from(
+            std::slice::from_ref(&base),
+            Some("claude-opus-4-6".to_string()),
+        )
+        .unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(hook_data.transcript_path, newer.to_string_lossy());
+        let json = serde_json::to_string(&hook_data).unwrap();
+        let parsed: HookData = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.transcript_path, hook_data.transcript_path);
+        assert_eq!(
+            parsed
+                .model
+                .id
+                .as_deref(),
+            Some("claude-opus-4-6")
+        );
+        assert_eq!(
+            parsed
+                .model
+                .display_name,
+            "Opus 4.6"
+        );
+    }
+
+    #[test]
+    fn test_resolve_context() {
+        let path = std::env::temp_dir().join("ccusage-test-context-mode.jsonl");
+        fs::write(
+            &path,
+            r#"{"timestamp":"2025-01-01T10:00:00Z","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":100,"output_tokens":5,"cache_read_input_tokens":77400}}}"#,
+        )
+        .unwrap();
+
+        let from_flag = resolve_context(Some(&path), None, true).unwrap();
+        assert_eq!(from_flag.tokens, 77_500);
+
+        let hook_input = serde_json::json!({
+            "session_id": "test",
+            "transcript_path": path,
+            "model": {"id": "claude-sonnet-4-5", "display_name": "Sonnet 4.5"},
+        })
+        .to_string();
+        let from_hook = resolve_context(None, Some(&hook_input), true).unwrap();
+        assert_eq!(from_hook.tokens, 77_500);
+        fs::remove_file(&path).unwrap();
+
+        assert!(resolve_context(Some(&path), None, true).is_err());
+        assert!(resolve_context(None, None, true).is_err());
+        assert!(resolve_context(None, Some("not json"), true).is_err());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("3d"), Ok(chrono::Duration::days(3)));
+        assert_eq!(parse_since("90m"), Ok(chrono::Duration::minutes(90)));
+        assert_eq!(parse_since("12h"), Ok(chrono::Duration::hours(12)));
+        assert_eq!(parse_since("1h30m"), Ok(chrono::Duration::minutes(90)));
+
+        for invalid in ["", "3", "d", "3x", "-3d", "0h", "1.5h", "3d 2h"] {
+            let err = parse_since(invalid).unwrap_err();
+            assert!(err.contains("invalid duration"), "{:?}: {}", invalid, err);
+        }
+
+        let cli =
+            Cli::try_parse_from(["ccusage-statusline-rs", "export-csv", "--since", "3d"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::ExportCsv { since: Some(since), .. }) if since == chrono::Duration::days(3)
+        ));
+        assert!(
+            Cli::try_parse_from(["ccusage-statusline-rs", "export-csv", "--since", "soon"])
+                .is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "ccusage-statusline-rs",
+                "export-csv",
+                "--since",
+                "3d",
+                "--days",
+                "7"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_color_choice() {
+        for stdout_is_terminal in [true, false] {
+            assert!(ColorChoice::Always.enabled(stdout_is_terminal));
+            assert!(!ColorChoice::Never.enabled(stdout_is_terminal));
+            assert_eq!(
+                ColorChoice::Auto.enabled(stdout_is_terminal),
+                stdout_is_terminal
+            );
+        }
+
+        for (arg, choice) in [
+            ("always", ColorChoice::Always),
+            ("auto", ColorChoice::Auto),
+            ("never", ColorChoice::Never),
+        ] {
+            let cli = Cli::try_parse_from(["ccusage-statusline-rs", "--color", arg]).unwrap();
+            assert_eq!(cli.color, Some(choice));
+        }
+        let cli = Cli::try_parse_from(["ccusage-statusline-rs"]).unwrap();
+        assert_eq!(cli.color, None);
+        assert!(Cli::try_parse_from(["ccusage-statusline-rs", "--color", "sometimes"]).is_err());
+    }
+
+    #[test]
+    fn test_in_element_order() {
+        let parts = vec![
+            (StatusElement::BlockCost, "cost".to_string()),
+            (StatusElement::BurnRate, "burn".to_string()),
+            (StatusElement::Context, "context".to_string()),
+        ];
+        let order = [StatusElement::Context, StatusElement::BlockCost];
+        assert_eq!(in_element_order(parts, &order), ["context", "cost", "burn"]);
+    }
+
     #[test]
     fn test_performance_under_20ms() {
         let _ = rustls::crypto::ring::default_provider().install_default();
-        let _ = run_interactive_mode();
+        let _ = run_interactive_mode(None);
 
         let iterations = 10;
         let mut total_duration = std::time::Duration::ZERO;
 
         for _ in 0..iterations {
             let start = std::time::Instant::now();
-            let _ = run_interactive_mode();
+            let _ = run_interactive_mode(None);
             total_duration += start.elapsed();
         }
 
@@ -0,0 +1,11 @@
+use std::time::Duration;
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+/// Blocking HTTP client whose requests give up after `timeout`
+/// (`network_timeout_secs`)
+pub fn client(timeout: Duration) -> reqwest::Result<reqwest::blocking::Client> {
+    reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+}
@@ -1,6 +1,30 @@
 use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Select a named account profile for this process (from `--profile`); a
+/// later call keeps the first profile
+pub fn set_profile(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        anyhow::bail!("Invalid profile name: {:?}", name);
+    }
+    let _ = PROFILE.set(name.to_string());
+    Ok(())
+}
+
+/// The profile selected with `set_profile`, if any
+pub fn profile() -> Option<&'static str> {
+    PROFILE
+        .get()
+        .map(String::as_str)
+}
 
 pub fn home_dir() -> Result<PathBuf> {
     std::env::var_os("HOME")
@@ -17,10 +41,55 @@ pub fn claude_config_dir() -> Result<PathBuf> {
     }
 }
 
+/// Credentials file for a profile: `.credentials-<name>.json` next to the
+/// default `.credentials.json`
+fn credentials_path_for(config_dir: &Path, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => config_dir.join(format!(".credentials-{}.json", name)),
+        None => config_dir.join(".credentials.json"),
+    }
+}
+
+pub fn credentials_path() -> Result<PathBuf> {
+    Ok(credentials_path_for(&claude_config_dir()?, profile()))
+}
+
+/// Data directory for a profile: `~/.claude-<name>/projects`
+fn profile_projects_dir(home: &Path, profile: &str) -> PathBuf {
+    home.join(format!(".claude-{}", profile))
+        .join("projects")
+}
+
+/// Name used to scope caches so accounts and profiles never share one. A
+/// profile wins over the config dir and is namespaced as `profile-<name>`,
+/// so it never matches a `CLAUDE_CONFIG_DIR` such as `~/.claude-<name>`.
+pub fn cache_scope_name(config_dir: &Path, profile: Option<&str>) -> String {
+    match profile {
+        Some(name) => format!("profile-{}", name),
+        None => config_dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(".claude")
+            .trim_start_matches('.')
+            .to_string(),
+    }
+}
+
 pub fn find_claude_paths() -> Result<Vec<PathBuf>> {
+    find_claude_paths_with(&[])
+}
+
+/// Like `find_claude_paths`, also including the existing `extra` project dirs
+/// (from `extra_project_paths` in the config)
+pub fn find_claude_paths_with(extra: &[PathBuf]) -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
 
-    if std::env::var("CLAUDE_CONFIG_DIR").is_ok() {
+    if let Some(name) = profile() {
+        let profile_path = profile_projects_dir(&home_dir()?, name);
+        if profile_path.exists() {
+            paths.push(profile_path);
+        }
+    } else if std::env::var("CLAUDE_CONFIG_DIR").is_ok() {
         let config_path = claude_config_dir()?.join("projects");
         if config_path.exists() {
             paths.push(config_path);
@@ -38,6 +107,7 @@ pub fn find_claude_paths() -> Result<Vec<PathBuf>> {
         }
     }
 
+    let paths = merge_project_paths(paths, extra);
     if paths.is_empty() {
         anyhow::bail!("No Claude data directories found");
     }
@@ -45,6 +115,22 @@ pub fn find_claude_paths() -> Result<Vec<PathBuf>> {
     Ok(paths)
 }
 
+/// Append the existing `extra` dirs to `paths`, dropping any that resolve to
+/// a directory already in the list
+fn merge_project_paths(paths: Vec<PathBuf>, extra: &[PathBuf]) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .chain(
+            extra
+                .iter()
+                .filter(|p| p.is_dir())
+                .cloned(),
+        )
+        .filter(|p| seen.insert(dunce::canonicalize(p).unwrap_or_else(|_| p.clone())))
+        .collect()
+}
+
 pub fn iter_jsonl_files(claude_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
     iter_jsonl_files_since(claude_paths, None)
 }
@@ -58,7 +144,10 @@ pub fn iter_jsonl_files_since(
 ) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
 
-    for base_path in claude_paths {
+    for (base, base_path) in claude_paths
+        .iter()
+        .enumerate()
+    {
         for project_entry in fs::read_dir(base_path)
             .with_context(|| format!("Failed to read directory: {}", base_path.display()))?
         {
@@ -91,11 +180,148 @@ pub fn iter_jsonl_files_since(
                     .and_then(|s| s.to_str())
                     == Some("jsonl")
                 {
-                    files.push(session_path);
+                    files.push((base, session_path));
                 }
             }
         }
     }
 
-    Ok(files)
+    Ok(dedup_across_bases(files))
+}
+
+fn content_hash(path: &Path) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Drop byte-identical copies of a session found at the same project-relative
+/// path under another base dir (e.g. copied between the old and new data
+/// dirs). Base dirs are already canonical-distinct, so only files whose
+/// project and name collide across bases are read.
+fn dedup_across_bases(files: Vec<(usize, PathBuf)>) -> Vec<PathBuf> {
+    let key = |path: &Path| -> (Option<OsString>, Option<OsString>) {
+        (
+            path.parent()
+                .and_then(Path::file_name)
+                .map(OsStr::to_os_string),
+            path.file_name()
+                .map(OsStr::to_os_string),
+        )
+    };
+    let mut bases_by_key: HashMap<_, HashSet<usize>> = HashMap::new();
+    for (base, path) in &files {
+        bases_by_key
+            .entry(key(path))
+            .or_default()
+            .insert(*base);
+    }
+
+    let mut hashes_by_key: HashMap<_, Vec<u64>> = HashMap::new();
+    files
+        .into_iter()
+        .filter_map(|(_, path)| {
+            let key = key(&path);
+            if bases_by_key[&key].len() < 2 {
+                return Some(path);
+            }
+            let Some(hash) = content_hash(&path) else {
+                return Some(path);
+            };
+            let seen = hashes_by_key
+                .entry(key)
+                .or_default();
+            if seen.contains(&hash) {
+                return None;
+            }
+            seen.push(hash);
+            Some(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_path_resolution() {
+        let config_dir = PathBuf::from("/home/u/.claude");
+        assert_eq!(
+            credentials_path_for(&config_dir, None),
+            PathBuf::from("/home/u/.claude/.credentials.json")
+        );
+        assert_eq!(
+            credentials_path_for(&config_dir, Some("work")),
+            PathBuf::from("/home/u/.claude/.credentials-work.json")
+        );
+        assert_eq!(
+            profile_projects_dir(Path::new("/home/u"), "work"),
+            PathBuf::from("/home/u/.claude-work/projects")
+        );
+
+        assert_eq!(cache_scope_name(&config_dir, None), "claude");
+        assert_eq!(cache_scope_name(&config_dir, Some("work")), "profile-work");
+        assert_ne!(
+            cache_scope_name(&config_dir, Some("work")),
+            cache_scope_name(Path::new("/home/u/.claude-work"), None)
+        );
+        assert_ne!(
+            cache_scope_name(&config_dir, Some("work")),
+            cache_scope_name(&config_dir, Some("personal"))
+        );
+    }
+
+    #[test]
+    fn test_extra_project_paths_merged_and_deduped() {
+        let base = std::env::temp_dir().join("ccusage-test-extra-paths");
+        let default_dir = base.join("default/projects");
+        let extra_dir = base.join("shared/projects");
+        fs::create_dir_all(&default_dir).unwrap();
+        fs::create_dir_all(&extra_dir).unwrap();
+
+        let merged = merge_project_paths(
+            vec![default_dir.clone()],
+            &[
+                extra_dir.clone(),
+                base.join("default/../default/projects"),
+                extra_dir.clone(),
+                base.join("missing/projects"),
+            ],
+        );
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(merged, vec![default_dir, extra_dir]);
+    }
+
+    #[test]
+    fn test_duplicate_session_across_base_paths() {
+        let base = std::env::temp_dir().join("ccusage-test-dup-dirs");
+        let old_dir = base.join("old/projects");
+        let new_dir = base.join("new/projects");
+        for dir in [&old_dir, &new_dir] {
+            fs::create_dir_all(dir.join("project")).unwrap();
+            fs::write(dir.join("project/session.jsonl"), "{\"same\":1}\n").unwrap();
+        }
+        fs::write(new_dir.join("project/other.jsonl"), "{\"same\":1}\n").unwrap();
+        fs::create_dir_all(old_dir.join("project2")).unwrap();
+        fs::write(old_dir.join("project2/session.jsonl"), "{\"diff\":2}\n").unwrap();
+
+        let files = iter_jsonl_files(&[old_dir, new_dir]).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        let mut names: Vec<_> = files
+            .iter()
+            .map(|p| {
+                p.strip_prefix(&base)
+                    .unwrap()
+                    .to_path_buf()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 3, "got {:?}", names);
+        assert!(names.contains(&PathBuf::from("new/projects/project/other.jsonl")));
+        assert!(names.contains(&PathBuf::from("old/projects/project2/session.jsonl")));
+    }
 }
@@ -1,13 +1,27 @@
+use crate::cache::force_refresh;
 use crate::types::{ModelPricing, PricingCache, TokenPrices, UsageData};
 use anyhow::{Context, Result};
 use chrono::Utc;
+use reqwest::blocking::Client;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 /// Pricing fetcher with caching
 pub struct PricingFetcher {
     models: HashMap<String, ModelPricing>,
+    /// Prices come from an expired cache because the refresh failed
+    stale: bool,
+}
+
+/// Pricing read from the cache or LiteLLM
+struct LoadedPricing {
+    models: HashMap<String, ModelPricing>,
+    /// When the pricing was fetched
+    timestamp: i64,
+    /// The cache had expired and a fresh fetch failed
+    stale: bool,
 }
 
 impl PricingFetcher {
@@ -16,12 +30,76 @@ impl PricingFetcher {
 
     /// Create a new pricing fetcher and load pricing data
     pub fn new(cache_dir: &Path) -> Result<Self> {
-        let models = Self::load_pricing(cache_dir)?;
-        Ok(Self { models })
+        Self::with_pruned_cache(
+            cache_dir,
+            false,
+            Duration::from_secs(crate::net::DEFAULT_TIMEOUT_SECS),
+        )
+    }
+
+    /// Like `new`, but when `pruned` is set, read Claude models from the
+    /// compact `pricing-pruned.json` while it is fresh. The full map is still
+    /// fetched and cached as usual, with requests bounded by `timeout`; the
+    /// pruned copy is rewritten from it.
+    pub fn with_pruned_cache(cache_dir: &Path, pruned: bool, timeout: Duration) -> Result<Self> {
+        let pruned_path = cache_dir.join("pricing-pruned.json");
+        if pruned
+            && !force_refresh()
+            && let Some(models) = Self::read_fresh_cache(&pruned_path)
+        {
+            return Ok(Self::from_models(models));
+        }
+
+        let loaded = Self::load_pricing(cache_dir, timeout)?;
+        if pruned {
+            let cache = PricingCache {
+                timestamp: loaded.timestamp,
+                models: prune_models(&loaded.models),
+            };
+            if let Ok(cache_json) = serde_json::to_string(&cache) {
+                let _ = fs::write(&pruned_path, cache_json);
+            }
+        }
+        Ok(Self {
+            models: loaded.models,
+            stale: loaded.stale,
+        })
+    }
+
+    /// Whether prices come from an expired cache because refreshing failed,
+    /// so they may miss a recent price change
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Models from a cache file younger than `MAX_AGE_SECONDS`
+    fn read_fresh_cache(path: &Path) -> Option<HashMap<String, ModelPricing>> {
+        let cache_file = fs::read_to_string(path).ok()?;
+        let cached = serde_json::from_str::<PricingCache>(&cache_file).ok()?;
+        let age = Utc::now().timestamp() - cached.timestamp;
+        (age < Self::MAX_AGE_SECONDS).then_some(cached.models)
+    }
+
+    /// Fetcher over a fixed model map, without touching the cache or network
+    pub fn from_models(models: HashMap<String, ModelPricing>) -> Self {
+        Self {
+            models,
+            stale: false,
+        }
     }
 
     /// Load pricing from cache or fetch from LiteLLM
-    fn load_pricing(cache_dir: &Path) -> Result<HashMap<String, ModelPricing>> {
+    fn load_pricing(cache_dir: &Path, timeout: Duration) -> Result<LoadedPricing> {
+        Self::load_pricing_from(cache_dir, Self::LITELLM_URL, || crate::net::client(timeout))
+    }
+
+    /// Like `load_pricing`, fetching `url` with the client from `client`,
+    /// only built when the cache is missing or expired
+    fn load_pricing_from(
+        cache_dir: &Path,
+        url: &str,
+        client: impl FnOnce() -> reqwest::Result<Client>,
+    ) -> Result<LoadedPricing> {
         let pricing_cache_path = cache_dir.join("pricing.json");
 
         // Check if cache exists and is fresh
@@ -31,25 +109,22 @@ impl PricingFetcher {
             let now = Utc::now().timestamp();
             let age = now - cached.timestamp;
 
-            if age < Self::MAX_AGE_SECONDS {
-                return Ok(cached.models);
+            if age < Self::MAX_AGE_SECONDS && !force_refresh() {
+                return Ok(LoadedPricing {
+                    models: cached.models,
+                    timestamp: cached.timestamp,
+                    stale: false,
+                });
             }
         }
 
         // Try to fetch fresh pricing
-        match reqwest::blocking::get(Self::LITELLM_URL) {
-            Ok(response)
-                if response
-                    .status()
-                    .is_success() =>
-            {
-                let models: HashMap<String, ModelPricing> = response
-                    .json()
-                    .context("Failed to parse pricing JSON")?;
-
+        match fetch_models(url, client) {
+            Ok(models) => {
                 // Cache the result
+                let timestamp = Utc::now().timestamp();
                 let cache = PricingCache {
-                    timestamp: Utc::now().timestamp(),
+                    timestamp,
                     models: models.clone(),
                 };
 
@@ -57,14 +132,22 @@ impl PricingFetcher {
                     let _ = fs::write(&pricing_cache_path, cache_json);
                 }
 
-                Ok(models)
+                Ok(LoadedPricing {
+                    models,
+                    timestamp,
+                    stale: false,
+                })
             }
-            _ => {
-                // Network error or bad response, try to use stale cache
+            Err(_) => {
+                // Network error, timeout or bad response, try to use stale cache
                 if let Ok(cache_file) = fs::read_to_string(&pricing_cache_path)
                     && let Ok(cached) = serde_json::from_str::<PricingCache>(&cache_file)
                 {
-                    return Ok(cached.models);
+                    return Ok(LoadedPricing {
+                        models: cached.models,
+                        timestamp: cached.timestamp,
+                        stale: true,
+                    });
                 }
                 anyhow::bail!("Failed to fetch pricing and no cache available")
             }
@@ -81,6 +164,13 @@ impl PricingFetcher {
             return Some(pricing);
         }
 
+        // Bedrock and Vertex ids map onto the canonical LiteLLM key
+        if let Some(canonical) = canonical_model_id(model_name)
+            && let Some(pricing) = self.get_model_pricing(&canonical)
+        {
+            return Some(pricing);
+        }
+
         // Try with common prefixes
         let prefixes = ["anthropic/", "claude-", "openai/"];
         for prefix in &prefixes {
@@ -111,17 +201,81 @@ impl PricingFetcher {
             .model
             && let Some(pricing) = self.get_model_pricing(model_name)
         {
-            return pricing.calculate_cost(
-                &entry
-                    .message
-                    .usage,
-            );
+            return pricing.calculate_cost(entry.usage());
         }
         // Fallback to hardcoded estimate if model not found
         estimate_cost_fallback(entry)
     }
 }
 
+/// Fetch the LiteLLM pricing map. The whole request, including reading the
+/// body, is bounded by the client's timeout (`network_timeout_secs` for
+/// `crate::net::client`) so a stalled connection cannot hang the statusline.
+fn fetch_models(
+    url: &str,
+    client: impl FnOnce() -> reqwest::Result<Client>,
+) -> Result<HashMap<String, ModelPricing>> {
+    let response = client()?
+        .get(url)
+        .send()
+        .context("Failed to fetch pricing")?;
+    if !response
+        .status()
+        .is_success()
+    {
+        anyhow::bail!("Pricing fetch returned status: {}", response.status());
+    }
+    response
+        .json()
+        .context("Failed to parse pricing JSON")
+}
+
+/// Keep only Claude models (`claude-*`, `anthropic/claude-*`, Bedrock and
+/// Vertex variants), the only ones transcripts are priced against
+fn prune_models(models: &HashMap<String, ModelPricing>) -> HashMap<String, ModelPricing> {
+    models
+        .iter()
+        .filter(|(name, _)| {
+            name.to_lowercase()
+                .contains("claude")
+        })
+        .map(|(name, pricing)| (name.clone(), pricing.clone()))
+        .collect()
+}
+
+/// Strip a trailing `-v2` / `-v2:0` version marker
+fn strip_version_suffix(id: &str) -> &str {
+    let is_number = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_digit())
+    };
+    match id.rsplit_once("-v") {
+        Some((base, version))
+            if version
+                .split(':')
+                .all(is_number) =>
+        {
+            base
+        }
+        _ => id,
+    }
+}
+
+/// Canonical LiteLLM key for a Bedrock id such as
+/// `us.anthropic.claude-3-5-sonnet-20241022-v2:0` or a Vertex id such as
+/// `claude-3-5-sonnet-v2@20241022`; `None` for other ids
+fn canonical_model_id(model_name: &str) -> Option<String> {
+    if let Some((region, rest)) = model_name.split_once("anthropic.")
+        && (region.is_empty() || region.ends_with('.'))
+    {
+        return Some(strip_version_suffix(rest).to_string());
+    }
+
+    let (name, date) = model_name.split_once('@')?;
+    Some(format!("{}-{}", strip_version_suffix(name), date))
+}
+
 /// Fallback cost estimation with hardcoded prices (when model not found in LiteLLM)
 fn estimate_cost_fallback(entry: &UsageData) -> f64 {
     let model = entry
@@ -165,9 +319,169 @@ fn estimate_cost_fallback(entry: &UsageData) -> f64 {
         ModelPricing::from_prices(base, tiered)
     };
 
-    pricing.calculate_cost(
-        &entry
-            .message
-            .usage,
-    )
+    pricing.calculate_cost(entry.usage())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_with_timeout(millis: u64) -> reqwest::Result<Client> {
+        Client::builder()
+            .timeout(std::time::Duration::from_millis(millis))
+            .build()
+    }
+
+    #[test]
+    fn test_stalled_fetch_times_out_to_stale_cache() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        // Accepts connections (via the backlog) but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/pricing.json",
+            listener
+                .local_addr()
+                .unwrap()
+        );
+
+        let dir = std::env::temp_dir().join("ccusage-test-pricing-timeout");
+        fs::create_dir_all(&dir).unwrap();
+        let stale = serde_json::json!({
+            "timestamp": Utc::now().timestamp() - 2 * PricingFetcher::MAX_AGE_SECONDS,
+            "models": { "claude-sonnet-4-5": { "input_cost_per_token": 3e-6 } }
+        });
+        fs::write(dir.join("pricing.json"), stale.to_string()).unwrap();
+
+        let start = std::time::Instant::now();
+        let result = PricingFetcher::load_pricing_from(&dir, &url, || client_with_timeout(200));
+        let elapsed = start.elapsed();
+        fs::remove_file(dir.join("pricing.json")).unwrap();
+        let no_cache = PricingFetcher::load_pricing_from(&dir, &url, || client_with_timeout(200));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let loaded = result.unwrap();
+        assert!(
+            loaded
+                .models
+                .contains_key("claude-sonnet-4-5")
+        );
+        assert!(loaded.stale);
+        assert!(
+            elapsed < std::time::Duration::from_secs(3),
+            "took {:?}",
+            elapsed
+        );
+        assert!(no_cache.is_err());
+    }
+
+    #[test]
+    fn test_fresh_cache_is_not_stale() {
+        let dir = std::env::temp_dir().join("ccusage-test-pricing-fresh");
+        fs::create_dir_all(&dir).unwrap();
+        let fresh = serde_json::json!({
+            "timestamp": Utc::now().timestamp(),
+            "models": { "claude-sonnet-4-5": { "input_cost_per_token": 3e-6 } }
+        });
+        fs::write(dir.join("pricing.json"), fresh.to_string()).unwrap();
+
+        // A fresh cache never reaches the (unroutable) URL
+        let loaded =
+            PricingFetcher::load_pricing_from(&dir, "http://0.0.0.0:1/", || client_with_timeout(1));
+        let fetcher =
+            PricingFetcher::with_pruned_cache(&dir, false, Duration::from_secs(5)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(
+            !loaded
+                .unwrap()
+                .stale
+        );
+        assert!(!fetcher.is_stale());
+    }
+
+    #[test]
+    fn test_bedrock_and_vertex_model_ids() {
+        let fetcher = PricingFetcher::from_models(
+            serde_json::from_value(serde_json::json!({
+                "claude-3-5-sonnet-20241022": { "input_cost_per_token": 3e-6 },
+                "claude-sonnet-4-20250514": { "input_cost_per_token": 3e-6 }
+            }))
+            .unwrap(),
+        );
+
+        for id in [
+            "anthropic.claude-3-5-sonnet-20241022-v2:0",
+            "us.anthropic.claude-3-5-sonnet-20241022-v2:0",
+            "claude-3-5-sonnet-v2@20241022",
+            "claude-3-5-sonnet@20241022",
+        ] {
+            let pricing = fetcher
+                .get_model_pricing(id)
+                .unwrap_or_else(|| panic!("no pricing for {}", id));
+            assert_eq!(pricing.input_cost_per_token, Some(3e-6), "{}", id);
+        }
+        assert!(
+            fetcher
+                .get_model_pricing("claude-sonnet-4@20250514")
+                .is_some()
+        );
+
+        assert_eq!(canonical_model_id("claude-3-5-sonnet-20241022"), None);
+        assert_eq!(
+            canonical_model_id("anthropic.claude-3-haiku-20240307-v1:0").as_deref(),
+            Some("claude-3-haiku-20240307")
+        );
+        assert_eq!(
+            canonical_model_id("anthropic.claude-instant-v1").as_deref(),
+            Some("claude-instant")
+        );
+    }
+
+    #[test]
+    fn test_pruned_cache_matches_full_entries() {
+        let dir = std::env::temp_dir().join("ccusage-test-pruned-pricing");
+        fs::create_dir_all(&dir).unwrap();
+        let full = serde_json::json!({
+            "timestamp": Utc::now().timestamp(),
+            "models": {
+                "claude-sonnet-4-5": { "input_cost_per_token": 3e-6, "output_cost_per_token": 15e-6 },
+                "anthropic/claude-opus-4-6": { "input_cost_per_token": 5e-6, "output_cost_per_token": 25e-6 },
+                "gpt-4o": { "input_cost_per_token": 2.5e-6, "output_cost_per_token": 10e-6 }
+            }
+        });
+        fs::write(dir.join("pricing.json"), full.to_string()).unwrap();
+
+        let full_fetcher =
+            PricingFetcher::with_pruned_cache(&dir, true, Duration::from_secs(5)).unwrap();
+        let pruned: PricingCache =
+            serde_json::from_str(&fs::read_to_string(dir.join("pricing-pruned.json")).unwrap())
+                .unwrap();
+        let pruned_fetcher =
+            PricingFetcher::with_pruned_cache(&dir, true, Duration::from_secs(5)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pruned.timestamp, full["timestamp"]);
+        let mut names: Vec<_> = pruned
+            .models
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        assert_eq!(names, ["anthropic/claude-opus-4-6", "claude-sonnet-4-5"]);
+        for (name, pricing) in &pruned.models {
+            assert_eq!(
+                serde_json::to_value(pricing).unwrap(),
+                serde_json::to_value(&full_fetcher.models[name]).unwrap()
+            );
+        }
+
+        let entry: UsageData = serde_json::from_str(
+            r#"{"timestamp":"2025-01-01T00:00:00Z","message":{"model":"claude-opus-4-6","usage":{"input_tokens":1000,"output_tokens":1000}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pruned_fetcher.calculate_entry_cost(&entry),
+            full_fetcher.calculate_entry_cost(&entry)
+        );
+    }
 }
@@ -1,9 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 /// Hook input data from Claude Code
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HookData {
     pub session_id: String,
     pub transcript_path: String,
@@ -14,19 +15,19 @@ pub struct HookData {
     pub context_window: Option<ContextWindowData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ModelInfo {
     #[serde(default)]
     pub id: Option<String>,
     pub display_name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Workspace {
     pub current_dir: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ContextWindowData {
     #[serde(default)]
     pub used_percentage: Option<f64>,
@@ -36,7 +37,7 @@ pub struct ContextWindowData {
     pub current_usage: Option<ContextUsage>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ContextUsage {
     #[serde(default)]
     pub input_tokens: u64,
@@ -47,7 +48,7 @@ pub struct ContextUsage {
 }
 
 /// Usage data entry from JSONL
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct UsageData {
     pub timestamp: String,
     pub message: MessageData,
@@ -55,16 +56,57 @@ pub struct UsageData {
     pub request_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+const NO_USAGE: UsageTokens = UsageTokens {
+    input_tokens: 0,
+    output_tokens: 0,
+    cache_creation_input_tokens: 0,
+    cache_read_input_tokens: 0,
+};
+
+impl UsageData {
+    /// Token usage, all zeros for lines without a usage object
+    pub fn usage(&self) -> &UsageTokens {
+        self.message
+            .usage
+            .as_ref()
+            .unwrap_or(&NO_USAGE)
+    }
+
+    pub fn has_usage(&self) -> bool {
+        self.message
+            .usage
+            .is_some()
+    }
+
+    /// `{messageId}:{requestId}` key used to skip duplicated entries
+    pub fn dedup_key(&self) -> Option<String> {
+        let msg_id = self
+            .message
+            .id
+            .as_ref()?;
+        let req_id = self
+            .request_id
+            .as_ref()?;
+        let mut hash = String::with_capacity(msg_id.len() + req_id.len() + 1);
+        hash.push_str(msg_id);
+        hash.push(':');
+        hash.push_str(req_id);
+        Some(hash)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct MessageData {
     #[serde(default)]
     pub model: Option<String>,
     #[serde(default)]
     pub id: Option<String>,
-    pub usage: UsageTokens,
+    /// Absent on user and tool-result lines
+    #[serde(default)]
+    pub usage: Option<UsageTokens>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct UsageTokens {
     pub input_tokens: u64,
     pub output_tokens: u64,
@@ -186,6 +228,64 @@ pub struct Semaphore {
     pub last_update_time: u64,
     pub transcript_path: String,
     pub transcript_mtime: u64,
+    #[serde(default)]
+    pub last_render_ms: u64,
+    #[serde(flatten, default)]
+    pub history: RenderHistory,
+}
+
+/// A 5h utilization reading and when it was first seen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UsageSample {
+    pub percent: f64,
+    pub at_secs: i64,
+}
+
+/// Values carried from one render to the next through the semaphore
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderHistory {
+    /// Active block cost shown by the last render
+    #[serde(default)]
+    pub last_cost_usd: Option<f64>,
+    /// Most recent distinct 5h utilization reading
+    #[serde(default)]
+    pub usage_sample: Option<UsageSample>,
+    /// The distinct reading before `usage_sample`
+    #[serde(default)]
+    pub prev_usage_sample: Option<UsageSample>,
+}
+
+impl RenderHistory {
+    /// Record a 5h utilization reading. The API value only changes every few
+    /// minutes, so repeats are ignored to keep two distinct samples apart.
+    pub fn record_usage(&mut self, percent: f64, now_secs: i64) {
+        if self
+            .usage_sample
+            .is_some_and(|s| s.percent == percent)
+        {
+            return;
+        }
+        self.prev_usage_sample = self.usage_sample;
+        self.usage_sample = Some(UsageSample {
+            percent,
+            at_secs: now_secs,
+        });
+    }
+}
+
+/// Statusline output together with the history to carry to the next render
+#[derive(Debug)]
+pub struct RenderedStatusline {
+    pub output: String,
+    pub history: RenderHistory,
+}
+
+/// The hook's transcript, parsed once and shared by the block scan and the
+/// context calculation
+#[derive(Debug)]
+pub struct Transcript {
+    pub path: PathBuf,
+    pub entries: Vec<UsageData>,
 }
 
 /// 5-hour billing block
@@ -194,8 +294,55 @@ pub struct Block {
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub cost_usd: f64,
+    /// Input, output and cache tokens across all entries
+    pub total_tokens: u64,
+    /// Cache write tokens, included in `total_tokens`
+    pub cache_creation_tokens: u64,
+    /// Cache read tokens, included in `total_tokens`
+    pub cache_read_tokens: u64,
     pub is_active: bool,
     pub hours_remaining: Option<f64>,
+    /// Distinct session files with entries inside this block
+    pub session_count: usize,
+    /// Newest entry is older than the configured stale-data threshold
+    pub stale: bool,
+    /// Block already ended but is kept within `block_expiry_grace_minutes`
+    pub in_grace: bool,
+    /// Transcript holding the block's newest entry (JSONL scans only)
+    pub latest_source: Option<PathBuf>,
+    /// Timestamp and cost of each entry, oldest first (JSONL scans only)
+    pub entry_costs: Vec<(DateTime<Utc>, f64)>,
+}
+
+impl Block {
+    /// Placeholder block starting now when no block is active
+    pub fn inactive(now: DateTime<Utc>, block_duration_hours: i64) -> Self {
+        Self {
+            start_time: now,
+            end_time: now + chrono::Duration::hours(block_duration_hours),
+            cost_usd: 0.0,
+            total_tokens: 0,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+            is_active: false,
+            hours_remaining: None,
+            session_count: 0,
+            stale: false,
+            in_grace: false,
+            latest_source: None,
+            entry_costs: Vec::new(),
+        }
+    }
+
+    /// Token count to display, with or without cache tokens
+    pub fn display_tokens(&self, include_cache: bool) -> u64 {
+        if include_cache {
+            self.total_tokens
+        } else {
+            self.total_tokens
+                .saturating_sub(self.cache_creation_tokens + self.cache_read_tokens)
+        }
+    }
 }
 
 /// Which limit is critical
@@ -244,6 +391,9 @@ impl Default for BurnRate {
 pub struct ContextInfo {
     pub tokens: u64,
     pub percentage: u32,
+    /// Tokens left before auto-compaction (or the context limit), 0 when
+    /// over. `None` when not looked up.
+    pub remaining: Option<u64>,
 }
 
 /// API usage data from Anthropic API
@@ -253,16 +403,19 @@ pub struct ApiUsageData {
     pub five_hour_resets_at: Option<DateTime<Utc>>,
     pub seven_day_percent: f64,
     pub seven_day_resets_at: Option<DateTime<Utc>>,
-    pub seven_day_sonnet_percent: f64,
+    /// `None` when the API omitted the Sonnet limit
+    pub seven_day_sonnet_percent: Option<f64>,
+    /// `None` when the API omitted the Opus limit
+    pub seven_day_opus_percent: Option<f64>,
+    /// Each account's usage, labelled A, B, … when several accounts are
+    /// configured; empty otherwise
+    pub accounts: Vec<(char, ApiUsageData)>,
 }
 
-/// Claude configuration from ~/.claude.json
+/// Claude configuration from ~/.claude.json or settings.json
 #[derive(Debug, Deserialize)]
 pub struct ClaudeConfig {
-    #[serde(default = "default_auto_compact", rename = "autoCompactEnabled")]
-    pub auto_compact_enabled: bool,
-}
-
-fn default_auto_compact() -> bool {
-    true
+    /// `None` when the file does not set it
+    #[serde(default, rename = "autoCompactEnabled")]
+    pub auto_compact_enabled: Option<bool>,
 }